@@ -19,16 +19,65 @@ impl Default for Color {
     }
 }
 
+/// Represents the style of the underline drawn under cells.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Default)]
+pub enum UnderlineStyle {
+    /// No underline.
+    #[default]
+    None,
+
+    /// A single straight underline (`CSI 4 m` or `CSI 4:1 m`).
+    Single,
+
+    /// A double underline (`CSI 21 m` or `CSI 4:2 m`).
+    Double,
+
+    /// A curly underline (`CSI 4:3 m`).
+    Curly,
+
+    /// A dotted underline (`CSI 4:4 m`).
+    Dotted,
+
+    /// A dashed underline (`CSI 4:5 m`).
+    Dashed,
+}
+
+/// Represents the semantic zone that a cell belongs to, as reported by the
+/// shell via OSC 133.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Default)]
+pub enum SemanticZone {
+    /// The cell isn't part of any known zone.
+    #[default]
+    None,
+
+    /// The cell is part of a prompt (`OSC 133 ; A`).
+    Prompt,
+
+    /// The cell is part of a command typed at a prompt (`OSC 133 ; B`).
+    Input,
+
+    /// The cell is part of the output of a command (`OSC 133 ; C`).
+    Output,
+}
+
 const TEXT_MODE_BOLD: u8 = 0b0000_0001;
 const TEXT_MODE_ITALIC: u8 = 0b0000_0010;
-const TEXT_MODE_UNDERLINE: u8 = 0b0000_0100;
-const TEXT_MODE_INVERSE: u8 = 0b0000_1000;
+const TEXT_MODE_INVERSE: u8 = 0b0000_0100;
+const TEXT_MODE_DIM: u8 = 0b0000_1000;
+const TEXT_MODE_BLINK: u8 = 0b0001_0000;
+const TEXT_MODE_HIDDEN: u8 = 0b0010_0000;
+const TEXT_MODE_STRIKETHROUGH: u8 = 0b0100_0000;
+const TEXT_MODE_OVERLINE: u8 = 0b1000_0000;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Attrs {
     pub fgcolor: Color,
     pub bgcolor: Color,
+    pub ulcolor: Color,
+    pub underline_style: UnderlineStyle,
     pub mode: u8,
+    // an id in the screen's hyperlink table
+    pub hyperlink: Option<std::num::NonZeroU32>,
 }
 
 impl Attrs {
@@ -57,15 +106,15 @@ impl Attrs {
     }
 
     pub fn underline(&self) -> bool {
-        self.mode & TEXT_MODE_UNDERLINE != 0
+        self.underline_style != UnderlineStyle::None
     }
 
     pub fn set_underline(&mut self, underline: bool) {
-        if underline {
-            self.mode |= TEXT_MODE_UNDERLINE;
+        self.underline_style = if underline {
+            UnderlineStyle::Single
         } else {
-            self.mode &= !TEXT_MODE_UNDERLINE;
-        }
+            UnderlineStyle::None
+        };
     }
 
     pub fn inverse(&self) -> bool {
@@ -80,12 +129,100 @@ impl Attrs {
         }
     }
 
+    pub fn dim(&self) -> bool {
+        self.mode & TEXT_MODE_DIM != 0
+    }
+
+    pub fn set_dim(&mut self, dim: bool) {
+        if dim {
+            self.mode |= TEXT_MODE_DIM;
+        } else {
+            self.mode &= !TEXT_MODE_DIM;
+        }
+    }
+
+    pub fn blink(&self) -> bool {
+        self.mode & TEXT_MODE_BLINK != 0
+    }
+
+    pub fn set_blink(&mut self, blink: bool) {
+        if blink {
+            self.mode |= TEXT_MODE_BLINK;
+        } else {
+            self.mode &= !TEXT_MODE_BLINK;
+        }
+    }
+
+    pub fn hidden(&self) -> bool {
+        self.mode & TEXT_MODE_HIDDEN != 0
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        if hidden {
+            self.mode |= TEXT_MODE_HIDDEN;
+        } else {
+            self.mode &= !TEXT_MODE_HIDDEN;
+        }
+    }
+
+    pub fn strikethrough(&self) -> bool {
+        self.mode & TEXT_MODE_STRIKETHROUGH != 0
+    }
+
+    pub fn set_strikethrough(&mut self, strikethrough: bool) {
+        if strikethrough {
+            self.mode |= TEXT_MODE_STRIKETHROUGH;
+        } else {
+            self.mode &= !TEXT_MODE_STRIKETHROUGH;
+        }
+    }
+
+    pub fn double_underline(&self) -> bool {
+        self.underline_style == UnderlineStyle::Double
+    }
+
+    pub fn overline(&self) -> bool {
+        self.mode & TEXT_MODE_OVERLINE != 0
+    }
+
+    pub fn set_overline(&mut self, overline: bool) {
+        if overline {
+            self.mode |= TEXT_MODE_OVERLINE;
+        } else {
+            self.mode &= !TEXT_MODE_OVERLINE;
+        }
+    }
+
+    // hyperlinks aren't text attributes, so they aren't reset by SGR 0
+    pub fn sgr_is_default(&self) -> bool {
+        self.fgcolor == Color::Default
+            && self.bgcolor == Color::Default
+            && self.ulcolor == Color::Default
+            && self.underline_style == UnderlineStyle::None
+            && self.mode == 0
+    }
+
+    pub fn reset_sgr(&mut self) {
+        *self = Self {
+            hyperlink: self.hyperlink,
+            ..Self::default()
+        };
+    }
+
     pub fn write_escape_code_diff(
         &self,
         contents: &mut Vec<u8>,
         other: &Self,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) {
-        if self != other && self == &Self::default() {
+        if self.hyperlink != other.hyperlink {
+            crate::term::Hyperlink::new(
+                self.hyperlink.and_then(|id| hyperlinks.get(id)),
+            )
+            .write_buf(contents);
+        }
+
+        if self.sgr_is_default() && !other.sgr_is_default() {
             crate::term::ClearAttrs::default().write_buf(contents);
             return;
         }
@@ -102,26 +239,64 @@ impl Attrs {
         } else {
             attrs.bgcolor(self.bgcolor)
         };
-        let attrs = if self.bold() == other.bold() {
-            attrs
+        // bold and dim are both turned off by the same escape code, so if
+        // either of them needs to be turned off, both need to be written
+        let attrs = if (other.bold() && !self.bold())
+            || (other.dim() && !self.dim())
+        {
+            attrs.bold(self.bold()).dim(self.dim())
         } else {
-            attrs.bold(self.bold())
+            let attrs = if self.bold() == other.bold() {
+                attrs
+            } else {
+                attrs.bold(self.bold())
+            };
+            if self.dim() == other.dim() {
+                attrs
+            } else {
+                attrs.dim(self.dim())
+            }
         };
         let attrs = if self.italic() == other.italic() {
             attrs
         } else {
             attrs.italic(self.italic())
         };
-        let attrs = if self.underline() == other.underline() {
+        let attrs = if self.underline_style == other.underline_style {
+            attrs
+        } else {
+            attrs.underline_style(self.underline_style)
+        };
+        let attrs = if self.ulcolor == other.ulcolor {
             attrs
         } else {
-            attrs.underline(self.underline())
+            attrs.ulcolor(self.ulcolor)
+        };
+        let attrs = if self.blink() == other.blink() {
+            attrs
+        } else {
+            attrs.blink(self.blink())
         };
         let attrs = if self.inverse() == other.inverse() {
             attrs
         } else {
             attrs.inverse(self.inverse())
         };
+        let attrs = if self.hidden() == other.hidden() {
+            attrs
+        } else {
+            attrs.hidden(self.hidden())
+        };
+        let attrs = if self.strikethrough() == other.strikethrough() {
+            attrs
+        } else {
+            attrs.strikethrough(self.strikethrough())
+        };
+        let attrs = if self.overline() == other.overline() {
+            attrs
+        } else {
+            attrs.overline(self.overline())
+        };
 
         attrs.write_buf(contents);
     }
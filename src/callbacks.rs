@@ -13,4 +13,43 @@ pub trait Callbacks {
     /// This callback is called when the terminal receives invalid input
     /// (such as an invalid UTF-8 character or an unused control character).
     fn error(&mut self, _: &mut crate::Screen) {}
+    /// This callback is called when the terminal needs to answer a query
+    /// from the running application (for instance, a color query like
+    /// `\e]11;?\a`). The reply should be written to the application's
+    /// input.
+    fn reply(&mut self, _: &mut crate::Screen, _reply: &[u8]) {}
+    /// This callback is called when the working directory reported by the
+    /// application (typically with `\e]7;file://<host>/<path>\a`) changes.
+    /// The new values are available via `Screen::working_directory` and
+    /// `Screen::working_directory_host`.
+    fn working_directory(&mut self, _: &mut crate::Screen) {}
+    /// This callback is called when the application finishes a synchronized
+    /// update (typically with `\e[?2026l`), or when one is cut short
+    /// because it exceeded the limits set via
+    /// `Parser::set_synchronized_output_limits`. The screen contains a
+    /// complete frame at this point, so this is a good time to render it.
+    fn synchronized_output_complete(&mut self, _: &mut crate::Screen) {}
+    /// This callback is called when the application requests to copy data
+    /// to the clipboard (typically with `\e]52;c;<base64 data>\a`). The
+    /// selection targets are given as the raw selection parameter (such as
+    /// `c` for the clipboard or `p` for the primary selection), and the data
+    /// has already been base64 decoded. Empty data means that the selection
+    /// should be cleared.
+    fn copy_to_clipboard(
+        &mut self,
+        _: &mut crate::Screen,
+        _selection: &[u8],
+        _data: &[u8],
+    ) {
+    }
+    /// This callback is called when the application requests the contents
+    /// of the clipboard (typically with `\e]52;c;?\a`). If this returns
+    /// data, it will be sent back to the application via `reply`.
+    fn paste_from_clipboard(
+        &mut self,
+        _: &mut crate::Screen,
+        _selection: &[u8],
+    ) -> Option<Vec<u8>> {
+        None
+    }
 }
@@ -68,6 +68,8 @@ impl Cell {
     pub(crate) fn clear(&mut self, attrs: crate::attrs::Attrs) {
         self.len = 0;
         self.attrs = attrs;
+        // erased cells never keep a hyperlink
+        self.attrs.hyperlink = None;
     }
 
     /// Returns the text contents of the cell.
@@ -151,16 +153,70 @@ impl Cell {
     }
 
     /// Returns whether the cell should be rendered with the underlined text
-    /// attribute.
+    /// attribute, in any style.
     #[must_use]
     pub fn underline(&self) -> bool {
         self.attrs.underline()
     }
 
+    /// Returns the style of the underline drawn under the cell.
+    #[must_use]
+    pub fn underline_style(&self) -> crate::UnderlineStyle {
+        self.attrs.underline_style
+    }
+
+    /// Returns the color of the underline drawn under the cell.
+    #[must_use]
+    pub fn underline_color(&self) -> crate::Color {
+        self.attrs.ulcolor
+    }
+
     /// Returns whether the cell should be rendered with the inverse text
     /// attribute.
     #[must_use]
     pub fn inverse(&self) -> bool {
         self.attrs.inverse()
     }
+
+    /// Returns whether the cell should be rendered with the dim text
+    /// attribute.
+    #[must_use]
+    pub fn dim(&self) -> bool {
+        self.attrs.dim()
+    }
+
+    /// Returns whether the cell should be rendered with the blink text
+    /// attribute.
+    #[must_use]
+    pub fn blink(&self) -> bool {
+        self.attrs.blink()
+    }
+
+    /// Returns whether the cell should be rendered with the hidden text
+    /// attribute.
+    #[must_use]
+    pub fn hidden(&self) -> bool {
+        self.attrs.hidden()
+    }
+
+    /// Returns whether the cell should be rendered with the strikethrough
+    /// text attribute.
+    #[must_use]
+    pub fn strikethrough(&self) -> bool {
+        self.attrs.strikethrough()
+    }
+
+    /// Returns whether the cell should be rendered with the double underline
+    /// text attribute.
+    #[must_use]
+    pub fn double_underline(&self) -> bool {
+        self.attrs.double_underline()
+    }
+
+    /// Returns whether the cell should be rendered with the overline text
+    /// attribute.
+    #[must_use]
+    pub fn overline(&self) -> bool {
+        self.attrs.overline()
+    }
 }
@@ -0,0 +1,114 @@
+use crate::term::BufWrite as _;
+
+/// A character set which can be designated into one of the G0-G3 slots.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    Uk,
+    DecSpecialGraphics,
+}
+
+impl Charset {
+    pub fn from_designator(b: u8) -> Option<Self> {
+        match b {
+            b'B' => Some(Self::Ascii),
+            b'A' => Some(Self::Uk),
+            b'0' => Some(Self::DecSpecialGraphics),
+            _ => None,
+        }
+    }
+
+    pub fn designator(self) -> u8 {
+        match self {
+            Self::Ascii => b'B',
+            Self::Uk => b'A',
+            Self::DecSpecialGraphics => b'0',
+        }
+    }
+
+    fn translate(self, c: char) -> char {
+        match self {
+            Self::Ascii => c,
+            Self::Uk => {
+                if c == '#' {
+                    '\u{a3}'
+                } else {
+                    c
+                }
+            }
+            Self::DecSpecialGraphics => match c {
+                '_' => ' ',
+                '`' => '\u{25c6}',
+                'a' => '\u{2592}',
+                'b' => '\u{2409}',
+                'c' => '\u{240c}',
+                'd' => '\u{240d}',
+                'e' => '\u{240a}',
+                'f' => '\u{b0}',
+                'g' => '\u{b1}',
+                'h' => '\u{2424}',
+                'i' => '\u{240b}',
+                'j' => '\u{2518}',
+                'k' => '\u{2510}',
+                'l' => '\u{250c}',
+                'm' => '\u{2514}',
+                'n' => '\u{253c}',
+                'o' => '\u{23ba}',
+                'p' => '\u{23bb}',
+                'q' => '\u{2500}',
+                'r' => '\u{23bc}',
+                's' => '\u{23bd}',
+                't' => '\u{251c}',
+                'u' => '\u{2524}',
+                'v' => '\u{2534}',
+                'w' => '\u{252c}',
+                'x' => '\u{2502}',
+                'y' => '\u{2264}',
+                'z' => '\u{2265}',
+                '{' => '\u{3c0}',
+                '|' => '\u{2260}',
+                '}' => '\u{a3}',
+                '~' => '\u{b7}',
+                _ => c,
+            },
+        }
+    }
+}
+
+/// The designated G0-G3 character sets, along with which of them is
+/// currently shifted in.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Charsets {
+    pub g: [Charset; 4],
+    pub gl: usize,
+    pub single_shift: Option<usize>,
+}
+
+impl Charsets {
+    pub fn designate(&mut self, slot: usize, charset: Charset) {
+        self.g[slot] = charset;
+    }
+
+    pub fn translate(&mut self, c: char) -> char {
+        let slot = self.single_shift.take().unwrap_or(self.gl);
+        self.g[slot].translate(c)
+    }
+
+    pub fn write_escape_code_diff(
+        &self,
+        contents: &mut Vec<u8>,
+        other: &Self,
+    ) {
+        for (slot, (charset, prev)) in self.g.iter().zip(&other.g).enumerate()
+        {
+            if charset != prev {
+                crate::term::DesignateCharset::new(slot, *charset)
+                    .write_buf(contents);
+            }
+        }
+        if self.gl != other.gl {
+            crate::term::LockingShift::new(self.gl).write_buf(contents);
+        }
+    }
+}
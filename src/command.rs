@@ -0,0 +1,76 @@
+/// Represents a single command found in the terminal contents via the
+/// semantic prompt marks (OSC 133) emitted by the shell.
+///
+/// Positions are given as (row, col), where rows are counted from the
+/// oldest row in the scrollback, so they are unaffected by the current
+/// scrollback position.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Command {
+    pub(crate) prompt: Option<(usize, u16)>,
+    pub(crate) input: Option<(usize, u16)>,
+    pub(crate) output: Option<(usize, u16)>,
+    pub(crate) end: (usize, u16),
+    pub(crate) exit_status: Option<i32>,
+}
+
+impl Command {
+    pub(crate) fn new(start: (usize, u16)) -> Self {
+        Self {
+            prompt: None,
+            input: None,
+            output: None,
+            end: start,
+            exit_status: None,
+        }
+    }
+
+    /// Returns the position where the prompt for this command starts, if
+    /// it is still available.
+    #[must_use]
+    pub fn prompt_start(&self) -> Option<(usize, u16)> {
+        self.prompt
+    }
+
+    /// Returns the position where the text typed at the prompt starts, if
+    /// the shell reported it.
+    #[must_use]
+    pub fn input_start(&self) -> Option<(usize, u16)> {
+        self.input
+    }
+
+    /// Returns the position where the output of the command starts, if the
+    /// command has started running.
+    #[must_use]
+    pub fn output_start(&self) -> Option<(usize, u16)> {
+        self.output
+    }
+
+    /// Returns the position just past the last cell belonging to this
+    /// command.
+    #[must_use]
+    pub fn end(&self) -> (usize, u16) {
+        self.end
+    }
+
+    /// Returns the exit status of the command, if it has finished and the
+    /// shell reported it.
+    #[must_use]
+    pub fn exit_status(&self) -> Option<i32> {
+        self.exit_status
+    }
+}
+
+// the semantic prompt state set by OSC 133, which is recorded on the row at
+// the first cell drawn after each mark (rather than on every cell) so that
+// commands can be found without looking at the cells themselves
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Mark {
+    pub zone: crate::attrs::SemanticZone,
+    // distinguishes the start of a new prompt from the continuation of the
+    // current one
+    pub prompt_start: bool,
+    // the exit status reported by OSC 133 D. if the next prompt starts
+    // before anything else is drawn, this ends up on the mark for that
+    // prompt, since the command it belongs to has already ended.
+    pub exit_status: Option<i32>,
+}
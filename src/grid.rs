@@ -13,6 +13,7 @@ pub struct Grid {
     scrollback: std::collections::VecDeque<crate::row::Row>,
     scrollback_len: usize,
     scrollback_offset: usize,
+    tabs: Vec<bool>,
 }
 
 impl Grid {
@@ -29,6 +30,7 @@ impl Grid {
             scrollback: std::collections::VecDeque::new(),
             scrollback_len,
             scrollback_offset: 0,
+            tabs: default_tabs(0, size.cols).collect(),
         }
     }
 
@@ -63,22 +65,42 @@ impl Grid {
         self.size
     }
 
-    pub fn set_size(&mut self, size: Size) {
-        if size.cols != self.size.cols {
+    // the alternate screen is redrawn by the application after a resize,
+    // so it is just truncated or padded to the new width rather than
+    // reflowed
+    pub fn set_size(&mut self, size: Size, reflow: bool) {
+        if self.scroll_bottom == self.size.rows - 1 {
+            self.scroll_bottom = size.rows - 1;
+        }
+
+        let reflow =
+            reflow && size.cols != self.size.cols && !self.rows.is_empty();
+        if reflow {
+            self.reflow(size);
+        } else if size.cols != self.size.cols {
             for row in &mut self.rows {
                 row.wrap(false);
+                row.resize(size.cols, crate::Cell::new());
             }
         }
 
-        if self.scroll_bottom == self.size.rows - 1 {
-            self.scroll_bottom = size.rows - 1;
-        }
-
         self.size = size;
-        for row in &mut self.rows {
-            row.resize(size.cols, crate::Cell::new());
-        }
         self.rows.resize(usize::from(size.rows), self.new_row());
+        // columns that are newly visible get the default tab stops, but
+        // anything that was explicitly set in the existing columns is kept
+        let tabs_len: u16 = self
+            .tabs
+            .len()
+            .try_into()
+            // tabs always has one entry per column, and we limit the number
+            // of cols to a u16 (see Size)
+            .unwrap();
+        self.tabs.truncate(usize::from(size.cols));
+        self.tabs.extend(default_tabs(tabs_len, size.cols));
+        if let Some(row) = self.rows.last_mut() {
+            // the row that would have been wrapped onto no longer exists
+            row.wrap(false);
+        }
 
         if self.scroll_bottom >= size.rows {
             self.scroll_bottom = size.rows - 1;
@@ -89,7 +111,124 @@ impl Grid {
 
         self.row_clamp_top(false);
         self.row_clamp_bottom(false);
-        self.col_clamp();
+        // reflowing can legitimately leave the cursor just past the end of
+        // a row (waiting to wrap), so only clamp it if we didn't reflow
+        if !reflow {
+            self.col_clamp();
+        }
+    }
+
+    // rejoins all of the rows in the scrollback and on the screen which
+    // were soft wrapped into logical lines, and lays those lines back out at
+    // the new width, keeping the cursor (and the saved cursor) on the same
+    // character it was on before.
+    fn reflow(&mut self, size: Size) {
+        let old_screen_top = self.scrollback.len();
+        let old_cursors = [self.pos, self.saved_pos].map(|pos| {
+            let row = pos.row.min(self.size.rows - 1);
+            (old_screen_top + usize::from(row), pos.col)
+        });
+        let old_rows: Vec<_> = self
+            .scrollback
+            .drain(..)
+            .chain(self.rows.drain(..))
+            .collect();
+
+        let mut new_rows: Vec<crate::row::Row> = vec![];
+        let mut cursors = [(0, 0); 2];
+        let mut screen_top = 0;
+        let mut line_start = 0;
+        while line_start < old_rows.len() {
+            let mut line_end = line_start;
+            while old_rows[line_end].wrapped()
+                && line_end + 1 < old_rows.len()
+            {
+                line_end += 1;
+            }
+            let line = &old_rows[line_start..=line_end];
+            let offset_of = |i: usize| -> usize {
+                line[..i - line_start]
+                    .iter()
+                    .map(|row| usize::from(row.cols()))
+                    .sum()
+            };
+
+            let line_cursors = old_cursors.map(|(row, col)| {
+                (line_start..=line_end).contains(&row).then(|| {
+                    (
+                        offset_of(row) + usize::from(col),
+                        col >= old_rows[row].cols(),
+                    )
+                })
+            });
+            let line_screen_top = (line_start..=line_end)
+                .contains(&old_screen_top)
+                .then(|| offset_of(old_screen_top));
+
+            let reflowed =
+                Reflow::new(line, size.cols, line_cursors, line_screen_top);
+            for (cursor, reflowed) in cursors.iter_mut().zip(reflowed.cursors)
+            {
+                if let Some((row, col)) = reflowed {
+                    *cursor = (new_rows.len() + row, col);
+                }
+            }
+            if let Some(row) = reflowed.screen_top {
+                screen_top = new_rows.len() + row;
+            }
+            new_rows.extend(reflowed.rows);
+
+            line_start = line_end + 1;
+        }
+
+        let [cursor, saved_cursor] = cursors;
+
+        // blank rows below the cursor can be reclaimed, so that lines which
+        // got longer don't push content off of the top of the screen
+        while new_rows.len() > cursor.0 + 1
+            && new_rows.last().is_some_and(crate::row::Row::is_blank)
+        {
+            new_rows.pop();
+        }
+
+        // if the lines above the cursor got shorter, pull rows back out of
+        // the scrollback to keep the cursor on the same row of the screen,
+        // and if they got longer, push the rows below the cursor down
+        // instead. either way, make sure the cursor stays visible and that
+        // we don't throw away more content than we have to.
+        let screen_top = screen_top
+            .min(cursor.0.saturating_sub(usize::from(self.pos.row)))
+            .max(new_rows.len().saturating_sub(usize::from(size.rows)))
+            .min(cursor.0);
+        let mut rows = new_rows.split_off(screen_top);
+        rows.truncate(usize::from(size.rows));
+        self.rows = rows;
+
+        self.scrollback = new_rows.into();
+        while self.scrollback.len() > self.scrollback_len {
+            self.scrollback.pop_front();
+        }
+        self.scrollback_offset =
+            self.scrollback_offset.min(self.scrollback.len());
+
+        self.pos = Pos {
+            // screen_top is clamped above to ensure that the cursor is on
+            // the screen, and the screen has at most u16 rows
+            row: (cursor.0 - screen_top).try_into().unwrap(),
+            col: cursor.1,
+        };
+        // the saved cursor may have ended up in the scrollback or below the
+        // screen, so clamp it the same way restore_cursor would
+        self.saved_pos = Pos {
+            row: saved_cursor
+                .0
+                .saturating_sub(screen_top)
+                .min(usize::from(size.rows - 1))
+                .try_into()
+                // just clamped to a u16 above
+                .unwrap(),
+            col: saved_cursor.1,
+        };
     }
 
     pub fn pos(&self) -> Pos {
@@ -166,7 +305,82 @@ impl Grid {
         self.scrollback
             .iter()
             .skip(scrollback_len - self.scrollback_offset)
-            .chain(self.rows.iter().take(rows_len - self.scrollback_offset))
+            // the scrollback offset can be larger than the screen height, in
+            // which case none of the rows on the screen are visible
+            .take(rows_len)
+            .chain(
+                self.rows
+                    .iter()
+                    .take(rows_len.saturating_sub(self.scrollback_offset)),
+            )
+    }
+
+    // the scrollback followed by the rows on the screen, regardless of the
+    // current scrollback position
+    pub fn all_rows(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &crate::row::Row> {
+        self.scrollback.iter().chain(self.rows.iter())
+    }
+
+    // the rows of all_rows up to and including the given row, from that row
+    // backwards
+    fn all_rows_rev_from(
+        &self,
+        row: usize,
+    ) -> impl Iterator<Item = (usize, &crate::row::Row)> {
+        let len = self.scrollback.len() + self.rows.len();
+        let row = row.min(len.saturating_sub(1));
+        self.all_rows()
+            .rev()
+            .skip(len.saturating_sub(row + 1))
+            .zip((0..=row).rev())
+            .map(|(r, i)| (i, r))
+    }
+
+    // the semantic prompt marks on all_rows, along with their positions
+    pub fn marks(
+        &self,
+    ) -> impl Iterator<Item = ((usize, u16), crate::command::Mark)> + '_ {
+        self.all_rows().enumerate().flat_map(|(i, row)| {
+            row.marks().map(move |(col, mark)| ((i, col), mark))
+        })
+    }
+
+    // the closest semantic prompt mark at or before the given position in
+    // all_rows
+    pub fn mark_before(
+        &self,
+        (row, col): (usize, u16),
+    ) -> Option<crate::command::Mark> {
+        self.all_rows_rev_from(row).find_map(|(i, r)| {
+            r.marks()
+                .rev()
+                .find(|(mark_col, _)| i < row || *mark_col <= col)
+                .map(|(_, mark)| mark)
+        })
+    }
+
+    // the position just past the last cell with contents between two
+    // positions in all_rows, or the start position if there isn't one
+    pub fn contents_end(
+        &self,
+        start: (usize, u16),
+        end: (usize, u16),
+    ) -> (usize, u16) {
+        for (i, row) in self.all_rows_rev_from(end.0) {
+            if i < start.0 {
+                break;
+            }
+            let from = if i == start.0 { start.1 } else { 0 };
+            let to = if i == end.0 { end.1 } else { row.cols() };
+            if let Some(col) = (from..to.min(row.cols())).rev().find(|col| {
+                row.get(*col).is_some_and(crate::Cell::has_contents)
+            }) {
+                return (i, col + 1);
+            }
+        }
+        start
     }
 
     pub fn drawing_rows(&self) -> impl Iterator<Item = &crate::row::Row> {
@@ -221,6 +435,10 @@ impl Grid {
         self.scrollback_offset
     }
 
+    pub fn scrollback_rows(&self) -> usize {
+        self.scrollback.len()
+    }
+
     pub fn set_scrollback(&mut self, rows: usize) {
         self.scrollback_offset = rows.min(self.scrollback.len());
     }
@@ -240,6 +458,34 @@ impl Grid {
         }
     }
 
+    // like write_contents, but between two positions in all_rows, with the
+    // end position being exclusive
+    pub fn write_contents_between(
+        &self,
+        contents: &mut String,
+        (start_row, start_col): (usize, u16),
+        (end_row, end_col): (usize, u16),
+    ) {
+        for (i, row) in self
+            .all_rows()
+            .enumerate()
+            .skip(start_row)
+            .take((end_row + 1).saturating_sub(start_row))
+        {
+            let start = if i == start_row { start_col } else { 0 };
+            let end = if i == end_row { end_col } else { row.cols() };
+            row.write_contents(
+                contents,
+                start,
+                end.saturating_sub(start),
+                false,
+            );
+            if i != end_row && !row.wrapped() {
+                contents.push('\n');
+            }
+        }
+    }
+
     /// Write data to draw the contents contains in `rows`, including
     /// sufficent control codes to redraw the terminal and establish correct
     /// terminal state.
@@ -250,11 +496,15 @@ impl Grid {
         &self,
         rows: R,
         contents: &mut Vec<u8>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs
         where R: Iterator<Item = &'a crate::row::Row>
     {
         crate::term::ClearAttrs::default().write_buf(contents);
         crate::term::ClearScreen::default().write_buf(contents);
+        if !self.has_default_tabs() {
+            self.write_tabs_formatted(contents);
+        }
 
         let mut prev_attrs = crate::attrs::Attrs::default();
         let mut prev_pos = Pos::default();
@@ -271,6 +521,7 @@ impl Grid {
                 wrapping,
                 Some(prev_pos),
                 Some(prev_attrs),
+                hyperlinks,
             );
             prev_pos = new_pos;
             prev_attrs = new_attrs;
@@ -281,6 +532,7 @@ impl Grid {
             contents,
             Some(prev_pos),
             Some(prev_attrs),
+            hyperlinks,
         );
 
         prev_attrs
@@ -291,8 +543,13 @@ impl Grid {
         contents: &mut Vec<u8>,
         prev: &Self,
         mut prev_attrs: crate::attrs::Attrs,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs {
         let mut prev_pos = prev.pos;
+        if self.tabs != prev.tabs {
+            self.write_tabs_formatted(contents);
+            prev_pos.col = 0;
+        }
         let mut wrapping = false;
         let mut prev_wrapping = false;
         for (i, (row, prev_row)) in
@@ -311,6 +568,7 @@ impl Grid {
                 prev_wrapping,
                 prev_pos,
                 prev_attrs,
+                hyperlinks,
             );
             prev_pos = new_pos;
             prev_attrs = new_attrs;
@@ -322,6 +580,7 @@ impl Grid {
             contents,
             Some(prev_pos),
             Some(prev_attrs),
+            hyperlinks,
         );
 
         prev_attrs
@@ -332,6 +591,7 @@ impl Grid {
         contents: &mut Vec<u8>,
         prev_pos: Option<Pos>,
         prev_attrs: Option<crate::attrs::Attrs>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) {
         let prev_attrs = prev_attrs.unwrap_or_default();
         // writing a character to the last column of a row doesn't wrap the
@@ -368,9 +628,17 @@ impl Grid {
                 } else {
                     crate::term::MoveTo::new(pos).write_buf(contents);
                 }
-                cell.attrs().write_escape_code_diff(contents, &prev_attrs);
+                cell.attrs().write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 contents.extend(cell.contents().as_bytes());
-                prev_attrs.write_escape_code_diff(contents, cell.attrs());
+                prev_attrs.write_escape_code_diff(
+                    contents,
+                    cell.attrs(),
+                    hyperlinks,
+                );
             } else {
                 // if the cell doesn't have contents, we can't have gotten
                 // here by drawing a character in the last column. this means
@@ -415,11 +683,13 @@ impl Grid {
                                 cell.attrs().write_escape_code_diff(
                                     contents,
                                     &prev_attrs,
+                                    hyperlinks,
                                 );
                                 contents.extend(cell.contents().as_bytes());
                                 prev_attrs.write_escape_code_diff(
                                     contents,
                                     cell.attrs(),
+                                    hyperlinks,
                                 );
                             }
                         } else {
@@ -427,11 +697,13 @@ impl Grid {
                             cell.attrs().write_escape_code_diff(
                                 contents,
                                 &prev_attrs,
+                                hyperlinks,
                             );
                             contents.extend(cell.contents().as_bytes());
                             prev_attrs.write_escape_code_diff(
                                 contents,
                                 cell.attrs(),
+                                hyperlinks,
                             );
                         }
                         contents.extend(
@@ -468,15 +740,20 @@ impl Grid {
                         // we assume self.pos.row is always valid, and
                         // self.size.cols - 1 is always a valid column
                         .unwrap();
-                    end_cell
-                        .attrs()
-                        .write_escape_code_diff(contents, &prev_attrs);
+                    end_cell.attrs().write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     crate::term::SaveCursor::default().write_buf(contents);
                     crate::term::Backspace::default().write_buf(contents);
                     crate::term::EraseChar::new(1).write_buf(contents);
                     crate::term::RestoreCursor::default().write_buf(contents);
-                    prev_attrs
-                        .write_escape_code_diff(contents, end_cell.attrs());
+                    prev_attrs.write_escape_code_diff(
+                        contents,
+                        end_cell.attrs(),
+                        hyperlinks,
+                    );
                 }
             }
         } else if let Some(prev_pos) = prev_pos {
@@ -634,6 +911,10 @@ impl Grid {
         self.pos.col = 0;
     }
 
+    pub fn scroll_region(&self) -> (u16, u16) {
+        (self.scroll_top, self.scroll_bottom)
+    }
+
     fn in_scroll_region(&self) -> bool {
         self.pos.row >= self.scroll_top && self.pos.row <= self.scroll_bottom
     }
@@ -642,6 +923,23 @@ impl Grid {
         self.scroll_top != 0 || self.scroll_bottom != self.size.rows - 1
     }
 
+    // the cursor position as reported by CPR, which is relative to the
+    // scroll region in origin mode and never past the last column
+    pub fn reported_pos(&self) -> Pos {
+        Pos {
+            row: if self.origin_mode {
+                self.pos.row.saturating_sub(self.scroll_top)
+            } else {
+                self.pos.row
+            },
+            col: self.pos.col.min(self.size.cols - 1),
+        }
+    }
+
+    pub fn origin_mode(&self) -> bool {
+        self.origin_mode
+    }
+
     pub fn set_origin_mode(&mut self, mode: bool) {
         self.origin_mode = mode;
         self.set_pos(Pos { row: 0, col: 0 });
@@ -703,12 +1001,81 @@ impl Grid {
         self.pos.col = self.pos.col.saturating_sub(count);
     }
 
-    pub fn col_tab(&mut self) {
-        self.pos.col -= self.pos.col % 8;
-        self.pos.col += 8;
+    pub fn col_tab(&mut self, count: u16) {
+        for _ in 0..count {
+            let next = self
+                .tabs
+                .iter()
+                .enumerate()
+                .skip(usize::from(self.pos.col) + 1)
+                .find(|(_, tab)| **tab);
+            if let Some((col, _)) = next {
+                // we limit the number of cols to a u16 (see Size)
+                self.pos.col = col.try_into().unwrap();
+            } else {
+                self.pos.col = self.size.cols - 1;
+                break;
+            }
+        }
+        self.col_clamp();
+    }
+
+    pub fn col_backtab(&mut self, count: u16) {
+        for _ in 0..count {
+            let prev = self
+                .tabs
+                .iter()
+                .enumerate()
+                .take(usize::from(self.pos.col))
+                .rev()
+                .find(|(_, tab)| **tab);
+            if let Some((col, _)) = prev {
+                // we limit the number of cols to a u16 (see Size)
+                self.pos.col = col.try_into().unwrap();
+            } else {
+                self.pos.col = 0;
+                break;
+            }
+        }
         self.col_clamp();
     }
 
+    pub fn set_tab(&mut self) {
+        if let Some(tab) = self.tabs.get_mut(usize::from(self.pos.col)) {
+            *tab = true;
+        }
+    }
+
+    pub fn clear_tab(&mut self) {
+        if let Some(tab) = self.tabs.get_mut(usize::from(self.pos.col)) {
+            *tab = false;
+        }
+    }
+
+    pub fn clear_all_tabs(&mut self) {
+        self.tabs.fill(false);
+    }
+
+    fn has_default_tabs(&self) -> bool {
+        self.tabs
+            .iter()
+            .copied()
+            .eq(default_tabs(0, self.size.cols))
+    }
+
+    pub fn write_tabs_formatted(&self, contents: &mut Vec<u8>) {
+        crate::term::ClearTabStops.write_buf(contents);
+        for (col, tab) in self.tabs.iter().enumerate() {
+            if *tab {
+                // we limit the number of cols to a u16 (see Size)
+                crate::term::MoveToCol::new(col.try_into().unwrap())
+                    .write_buf(contents);
+                crate::term::SetTabStop.write_buf(contents);
+            }
+        }
+        crate::term::CarriageReturn.write_buf(contents);
+    }
+
     pub fn col_set(&mut self, i: u16) {
         self.pos.col = i;
         self.col_clamp();
@@ -761,13 +1128,150 @@ impl Grid {
         }
     }
 
-    fn col_clamp(&mut self) {
+    pub fn col_clamp(&mut self) {
         if self.pos.col > self.size.cols - 1 {
             self.pos.col = self.size.cols - 1;
         }
     }
 }
 
+// the result of laying out a single logical line at a new width
+struct Reflow {
+    rows: Vec<crate::row::Row>,
+    cursors: [Option<(usize, u16)>; 2],
+    screen_top: Option<usize>,
+}
+
+impl Reflow {
+    fn new(
+        line: &[crate::row::Row],
+        cols: u16,
+        cursors: [Option<(usize, bool)>; 2],
+        screen_top: Option<usize>,
+    ) -> Self {
+        let cells: Vec<_> =
+            line.iter().flat_map(crate::row::Row::cells).collect();
+
+        // trailing empty cells are dropped and recreated at the end of the
+        // new last row, to avoid wrapping a row full of nothing (but still
+        // keeping things like a background color that fills the row)
+        let fill = cells
+            .last()
+            .map_or_else(crate::attrs::Attrs::default, |cell| *cell.attrs());
+        let mut len = cells.len();
+        while len > 0
+            && !cells[len - 1].has_contents()
+            && cells[len - 1].attrs() == &fill
+        {
+            len -= 1;
+        }
+
+        // semantic prompt marks move along with the cells they are on
+        let mut offset = 0;
+        let mut marks = vec![];
+        for row in line {
+            marks.extend(
+                row.marks()
+                    .map(|(col, mark)| (offset + usize::from(col), mark)),
+            );
+            offset += usize::from(row.cols());
+        }
+        let mut marks = marks.into_iter().peekable();
+
+        let mut rows = vec![crate::row::Row::new(cols)];
+        let mut new_cursors = [None; 2];
+        let mut new_screen_top = None;
+        let mut col = 0;
+        let mut i = 0;
+        while i < len {
+            let width: u16 = if cells[i].is_wide() && i + 1 < cells.len() {
+                2
+            } else {
+                1
+            };
+            let unit = i..i + usize::from(width);
+            if col > 0 && col + width > cols {
+                // like Screen::text, a wide character which doesn't fit in
+                // the last column starts a new line rather than wrapping
+                rows.last_mut()
+                    // rows always contains at least one row
+                    .unwrap()
+                    .wrap(col >= cols);
+                rows.push(crate::row::Row::new(cols));
+                col = 0;
+            }
+
+            for (cursor, new_cursor) in cursors.iter().zip(&mut new_cursors) {
+                if cursor.is_some_and(|(offset, _)| unit.contains(&offset)) {
+                    *new_cursor = Some((rows.len() - 1, col));
+                }
+            }
+            if screen_top.is_some_and(|offset| unit.contains(&offset)) {
+                new_screen_top = Some(rows.len() - 1);
+            }
+
+            let row = rows
+                .last_mut()
+                // rows always contains at least one row
+                .unwrap();
+            while let Some((_, mark)) =
+                marks.next_if(|(offset, _)| *offset < unit.end)
+            {
+                row.set_mark(col, mark);
+            }
+            for cell in &cells[unit] {
+                if let Some(new_cell) = row.get_mut(col) {
+                    *new_cell = (*cell).clone();
+                }
+                col += 1;
+            }
+            i += usize::from(width);
+        }
+
+        let last = rows.len() - 1;
+        let row = rows
+            .last_mut()
+            // rows always contains at least one row
+            .unwrap();
+        for col in col..cols {
+            // we're iterating over the columns of a row of width cols
+            row.get_mut(col).unwrap().clear(fill);
+        }
+
+        for (cursor, new_cursor) in cursors.iter().zip(&mut new_cursors) {
+            if let Some((offset, past_end)) = *cursor {
+                if offset >= len {
+                    // the cursor is past the end of the line's contents, so
+                    // leave it the same distance past the end of the new
+                    // last row. it can only move into the wrapping position
+                    // just after the last column if it was already there, or
+                    // if the contents it was directly after now end at the
+                    // last column.
+                    let max_col = if past_end { cols } else { cols - 1 };
+                    let extra = (offset - len).try_into().unwrap_or(u16::MAX);
+                    *new_cursor = Some((
+                        last,
+                        col.saturating_add(extra).min(max_col).max(col),
+                    ));
+                }
+            }
+        }
+        if screen_top.is_some_and(|offset| offset >= len) {
+            new_screen_top = Some(last);
+        }
+
+        Self {
+            rows,
+            cursors: new_cursors,
+            screen_top: new_screen_top,
+        }
+    }
+}
+
+fn default_tabs(start: u16, cols: u16) -> impl Iterator<Item = bool> {
+    (start..cols).map(|col| col > 0 && col % 8 == 0)
+}
+
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Size {
     pub rows: u16,
@@ -0,0 +1,77 @@
+/// Represents a hyperlink attached to cells via OSC 8.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Hyperlink {
+    uri: String,
+    id: Option<String>,
+}
+
+impl Hyperlink {
+    pub(crate) fn new(uri: String, id: Option<String>) -> Self {
+        Self { uri, id }
+    }
+
+    /// Returns the URI that the hyperlink points to.
+    #[must_use]
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the explicit id of the hyperlink, if one was given. Cells
+    /// with the same URI and id are meant to be treated as a single link,
+    /// even if they aren't adjacent.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
+// the hyperlinks used by the cells of a screen. cells refer to them by id,
+// so that attrs can stay small and Copy. ids aren't reused (until they wrap
+// around), so that cells copied from an earlier state of the screen keep
+// referring to the same link.
+#[derive(Clone, Debug)]
+pub struct Hyperlinks {
+    links: std::collections::HashMap<std::num::NonZeroU32, Hyperlink>,
+    ids: std::collections::HashMap<Hyperlink, std::num::NonZeroU32>,
+    next_id: std::num::NonZeroU32,
+}
+
+impl Default for Hyperlinks {
+    fn default() -> Self {
+        Self {
+            links: std::collections::HashMap::new(),
+            ids: std::collections::HashMap::new(),
+            next_id: std::num::NonZeroU32::MIN,
+        }
+    }
+}
+
+impl Hyperlinks {
+    pub fn get(&self, id: std::num::NonZeroU32) -> Option<&Hyperlink> {
+        self.links.get(&id)
+    }
+
+    pub fn id(&self, hyperlink: &Hyperlink) -> Option<std::num::NonZeroU32> {
+        self.ids.get(hyperlink).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn insert(&mut self, hyperlink: Hyperlink) -> std::num::NonZeroU32 {
+        let id = self.next_id;
+        self.next_id = id.checked_add(1).unwrap_or(std::num::NonZeroU32::MIN);
+        self.ids.insert(hyperlink.clone(), id);
+        self.links.insert(id, hyperlink);
+        id
+    }
+
+    pub fn retain(
+        &mut self,
+        used: &std::collections::HashSet<std::num::NonZeroU32>,
+    ) {
+        self.links.retain(|id, _| used.contains(id));
+        self.ids.retain(|_, id| used.contains(id));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::term::BufWrite as _;
+
+const KITTY_REPORT_EVENT_TYPES: u8 = 0b0_0010;
+const KITTY_REPORT_ALTERNATE_KEYS: u8 = 0b0_0100;
+const KITTY_REPORT_ALL_KEYS: u8 = 0b0_1000;
+const KITTY_REPORT_TEXT: u8 = 0b1_0000;
+
+/// A key which can be sent to the application running in the terminal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Key {
+    /// A key which produces text, given as the character it produces
+    /// without any modifiers (for instance, `a` rather than `A` when shift
+    /// is held).
+    Char(char),
+    /// The enter (or return) key.
+    Enter,
+    /// The tab key. Shift+tab is sent as a back tab.
+    Tab,
+    /// The backspace key.
+    Backspace,
+    /// The escape key.
+    Escape,
+    /// The up arrow key.
+    Up,
+    /// The down arrow key.
+    Down,
+    /// The right arrow key.
+    Right,
+    /// The left arrow key.
+    Left,
+    /// The home key.
+    Home,
+    /// The end key.
+    End,
+    /// The insert key.
+    Insert,
+    /// The delete key (as opposed to backspace).
+    Delete,
+    /// The page up key.
+    PageUp,
+    /// The page down key.
+    PageDown,
+    /// A function key, from F1 to F12.
+    F(u8),
+    /// A key on the numeric keypad, given as the character it produces
+    /// (`0` through `9`, `.`, `/`, `*`, `-`, `+`, or `=`).
+    Keypad(char),
+    /// The enter key on the numeric keypad.
+    KeypadEnter,
+}
+
+/// The modifier keys held down during a key event.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+    pub super_: bool,
+}
+
+impl Modifiers {
+    // the modifier parameter used by both xterm and the kitty keyboard
+    // protocol
+    fn param(self) -> u16 {
+        1 + u16::from(self.shift)
+            + 2 * u16::from(self.alt)
+            + 4 * u16::from(self.ctrl)
+            + 8 * u16::from(self.super_)
+    }
+}
+
+/// The kind of a key event. Repeat and release events are only reported
+/// to applications which have requested them via the kitty keyboard
+/// protocol.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum KeyEventKind {
+    /// The key was pressed.
+    #[default]
+    Press,
+    /// The key is being held down, and is repeating.
+    Repeat,
+    /// The key was released.
+    Release,
+}
+
+/// A key event to be sent to the application running in the terminal, to
+/// be encoded with `Screen::key_event`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+    pub kind: KeyEventKind,
+    /// The text produced by the key event, if it differs from what the
+    /// key produces on its own (for instance, `A` for shift+a, or the
+    /// result of a dead key or input method).
+    pub text: Option<String>,
+}
+
+impl KeyEvent {
+    /// Creates a key press event for the given key and modifiers.
+    #[must_use]
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            modifiers,
+            kind: KeyEventKind::Press,
+            text: None,
+        }
+    }
+
+    pub(crate) fn write_buf(&self, buf: &mut Vec<u8>, modes: &InputModes) {
+        if modes.kitty_flags == 0 {
+            self.write_legacy(buf, modes);
+        } else {
+            self.write_kitty(buf, modes.kitty_flags);
+        }
+    }
+
+    fn write_legacy(&self, buf: &mut Vec<u8>, modes: &InputModes) {
+        if self.kind == KeyEventKind::Release {
+            return;
+        }
+
+        let mods = self.modifiers;
+        let alt_prefix = |buf: &mut Vec<u8>| {
+            if mods.alt {
+                buf.push(b'\x1b');
+            }
+        };
+        match self.key {
+            Key::Char(c) => {
+                alt_prefix(buf);
+                match ctrl_char(c).filter(|_| mods.ctrl) {
+                    Some(b) => buf.push(b),
+                    None => buf.extend_from_slice(self.text().as_bytes()),
+                }
+            }
+            Key::Enter => {
+                alt_prefix(buf);
+                if modes.newline {
+                    buf.extend_from_slice(b"\r\n");
+                } else {
+                    buf.push(b'\r');
+                }
+            }
+            Key::Tab if mods.shift => {
+                crate::term::KeyCsi::new(None, None, None, None, b'Z')
+                    .write_buf(buf);
+            }
+            Key::Tab => {
+                alt_prefix(buf);
+                buf.push(b'\t');
+            }
+            Key::Backspace => {
+                alt_prefix(buf);
+                buf.push(if mods.ctrl { b'\x08' } else { b'\x7f' });
+            }
+            Key::Escape => {
+                alt_prefix(buf);
+                buf.push(b'\x1b');
+            }
+            Key::Keypad(c) => match keypad_application_code(c) {
+                Some(code) if modes.application_keypad => {
+                    crate::term::KeySs3::new(code).write_buf(buf);
+                }
+                _ => {
+                    alt_prefix(buf);
+                    buf.extend_from_slice(self.text().as_bytes());
+                }
+            },
+            Key::KeypadEnter if modes.application_keypad => {
+                crate::term::KeySs3::new(b'M').write_buf(buf);
+            }
+            Key::KeypadEnter => {
+                Self::new(Key::Enter, mods).write_legacy(buf, modes);
+            }
+            key => {
+                let cursor_key = matches!(
+                    key,
+                    Key::Up
+                        | Key::Down
+                        | Key::Right
+                        | Key::Left
+                        | Key::Home
+                        | Key::End
+                );
+                match functional_key_code(key, false) {
+                    Some(FunctionalKeyCode::Letter(letter))
+                        if mods.param() == 1
+                            && (!cursor_key || modes.application_cursor) =>
+                    {
+                        // F1-F4 and cursor keys in application cursor mode
+                        // are sent with SS3
+                        crate::term::KeySs3::new(letter).write_buf(buf);
+                    }
+                    Some(code) => {
+                        code.write_buf(buf, mods.param(), None);
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn write_kitty(&self, buf: &mut Vec<u8>, flags: u8) {
+        let report_all_keys = flags & KITTY_REPORT_ALL_KEYS != 0;
+        let kind = if flags & KITTY_REPORT_EVENT_TYPES == 0 {
+            if self.kind == KeyEventKind::Release {
+                return;
+            }
+            KeyEventKind::Press
+        } else {
+            self.kind
+        };
+        let mods = self.modifiers;
+
+        if !report_all_keys {
+            match self.key {
+                Key::Char(_) | Key::Keypad(_)
+                    if kind != KeyEventKind::Release
+                        && !mods.alt
+                        && !mods.ctrl
+                        && !mods.super_ =>
+                {
+                    buf.extend_from_slice(self.text().as_bytes());
+                    return;
+                }
+                // these are left alone so that the user can still type
+                // `reset` if the application crashes without restoring the
+                // keyboard mode
+                Key::Enter | Key::Tab | Key::Backspace | Key::KeypadEnter
+                    if kind == KeyEventKind::Release =>
+                {
+                    return;
+                }
+                Key::Enter | Key::KeypadEnter if mods.param() == 1 => {
+                    buf.push(b'\r');
+                    return;
+                }
+                Key::Tab if mods.param() == 1 => {
+                    buf.push(b'\t');
+                    return;
+                }
+                Key::Backspace if mods.param() == 1 => {
+                    buf.push(b'\x7f');
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let event = match kind {
+            KeyEventKind::Press => None,
+            KeyEventKind::Repeat => Some(2),
+            KeyEventKind::Release => Some(3),
+        };
+        if let Some(code) = functional_key_code(self.key, true) {
+            code.write_buf(buf, mods.param(), event);
+            return;
+        }
+        let Some(key_code) = kitty_key_code(self.key) else {
+            return;
+        };
+
+        let text = self.text();
+        let alternate = if flags & KITTY_REPORT_ALTERNATE_KEYS != 0
+            && mods.shift
+        {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(shifted), None) if u32::from(shifted) != key_code => {
+                    Some(u32::from(shifted))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+        let text = (flags & KITTY_REPORT_TEXT != 0
+            && report_all_keys
+            && kind != KeyEventKind::Release
+            && matches!(self.key, Key::Char(_) | Key::Keypad(_))
+            && (self.text.is_some()
+                || !(mods.alt || mods.ctrl || mods.super_))
+            && !text.chars().any(char::is_control))
+        .then_some(text.as_str());
+        let modifiers =
+            (mods.param() != 1 || event.is_some() || text.is_some())
+                .then_some((mods.param(), event));
+        crate::term::KeyCsi::new(
+            Some(key_code),
+            alternate,
+            modifiers,
+            text,
+            b'u',
+        )
+        .write_buf(buf);
+    }
+
+    fn text(&self) -> String {
+        if let Some(text) = &self.text {
+            return text.clone();
+        }
+        match self.key {
+            Key::Char(c) if self.modifiers.shift => {
+                c.to_uppercase().collect()
+            }
+            Key::Char(c) | Key::Keypad(c) => c.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+// the input modes which affect how key events are encoded
+pub struct InputModes {
+    pub application_cursor: bool,
+    pub application_keypad: bool,
+    pub newline: bool,
+    pub kitty_flags: u8,
+}
+
+// the ways that keys which don't produce text are sent
+#[derive(Copy, Clone)]
+enum FunctionalKeyCode {
+    // CSI 1 ; modifiers <letter>
+    Letter(u8),
+    // CSI <number> ; modifiers ~
+    Tilde(u16),
+}
+
+impl FunctionalKeyCode {
+    fn write_buf(self, buf: &mut Vec<u8>, param: u16, event: Option<u8>) {
+        let modifiers =
+            (param != 1 || event.is_some()).then_some((param, event));
+        // the number can be left out for letters when there is nothing
+        // after it
+        let (number, terminator) = match self {
+            Self::Letter(letter) => (modifiers.map(|_| 1), letter),
+            Self::Tilde(number) => (Some(u32::from(number)), b'~'),
+        };
+        crate::term::KeyCsi::new(number, None, modifiers, None, terminator)
+            .write_buf(buf);
+    }
+}
+
+fn functional_key_code(key: Key, kitty: bool) -> Option<FunctionalKeyCode> {
+    Some(match key {
+        Key::Up => FunctionalKeyCode::Letter(b'A'),
+        Key::Down => FunctionalKeyCode::Letter(b'B'),
+        Key::Right => FunctionalKeyCode::Letter(b'C'),
+        Key::Left => FunctionalKeyCode::Letter(b'D'),
+        Key::Home => FunctionalKeyCode::Letter(b'H'),
+        Key::End => FunctionalKeyCode::Letter(b'F'),
+        Key::Insert => FunctionalKeyCode::Tilde(2),
+        Key::Delete => FunctionalKeyCode::Tilde(3),
+        Key::PageUp => FunctionalKeyCode::Tilde(5),
+        Key::PageDown => FunctionalKeyCode::Tilde(6),
+        Key::F(1) => FunctionalKeyCode::Letter(b'P'),
+        Key::F(2) => FunctionalKeyCode::Letter(b'Q'),
+        // the kitty protocol avoids CSI R, since it is ambiguous with a
+        // cursor position report
+        Key::F(3) if kitty => FunctionalKeyCode::Tilde(13),
+        Key::F(3) => FunctionalKeyCode::Letter(b'R'),
+        Key::F(4) => FunctionalKeyCode::Letter(b'S'),
+        Key::F(n @ 5) => FunctionalKeyCode::Tilde(u16::from(n) + 10),
+        Key::F(n @ 6..=10) => FunctionalKeyCode::Tilde(u16::from(n) + 11),
+        Key::F(n @ 11..=12) => FunctionalKeyCode::Tilde(u16::from(n) + 12),
+        Key::F(_)
+        | Key::Enter
+        | Key::Tab
+        | Key::Backspace
+        | Key::Escape
+        | Key::Char(_)
+        | Key::Keypad(_)
+        | Key::KeypadEnter => return None,
+    })
+}
+
+// the key code used by the kitty keyboard protocol for keys which are sent
+// as CSI u
+fn kitty_key_code(key: Key) -> Option<u32> {
+    Some(match key {
+        Key::Enter => 13,
+        Key::Tab => 9,
+        Key::Backspace => 127,
+        Key::Escape => 27,
+        Key::Char(c) => c.into(),
+        Key::Keypad(c) => match c {
+            '0'..='9' => 57399 + u32::from(c) - u32::from('0'),
+            '.' => 57409,
+            '/' => 57410,
+            '*' => 57411,
+            '-' => 57412,
+            '+' => 57413,
+            '=' => 57415,
+            _ => return None,
+        },
+        Key::KeypadEnter => 57414,
+        _ => return None,
+    })
+}
+
+// the final byte of the SS3 sequence sent for keypad keys in application
+// keypad mode
+fn keypad_application_code(c: char) -> Option<u8> {
+    Some(match c {
+        '0'..='9' => b'p' + u8::try_from(c).ok()? - b'0',
+        '.' => b'n',
+        '/' => b'o',
+        '*' => b'j',
+        '-' => b'm',
+        '+' => b'k',
+        '=' => b'X',
+        _ => return None,
+    })
+}
+
+// the control character that xterm sends for ctrl+<c>
+fn ctrl_char(c: char) -> Option<u8> {
+    Some(match c {
+        'a'..='z' | 'A'..='Z' => u8::try_from(c).ok()? & 0x1f,
+        '@' | ' ' | '2' => 0x00,
+        '[' | '3' => 0x1b,
+        '\\' | '4' => 0x1c,
+        ']' | '5' => 0x1d,
+        '^' | '~' | '6' => 0x1e,
+        '_' | '/' | '7' => 0x1f,
+        '?' | '8' => 0x7f,
+        _ => return None,
+    })
+}
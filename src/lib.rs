@@ -47,17 +47,30 @@
 #![allow(clippy::type_complexity)]
 
 mod attrs;
+mod base64;
 mod callbacks;
 mod cell;
+mod charset;
+mod command;
 mod grid;
+mod hyperlink;
+mod key;
+mod mouse;
+mod palette;
 mod parser;
+mod paste;
 mod perform;
 mod row;
 mod screen;
 mod term;
 
-pub use attrs::Color;
+pub use attrs::{Color, SemanticZone, UnderlineStyle};
 pub use callbacks::Callbacks;
 pub use cell::Cell;
+pub use command::Command;
+pub use hyperlink::Hyperlink;
+pub use key::{Key, KeyEvent, KeyEventKind, Modifiers};
+pub use mouse::{MouseButton, MouseEvent, MouseEventKind};
 pub use parser::Parser;
+pub use paste::Paste;
 pub use screen::{MouseProtocolEncoding, MouseProtocolMode, Screen};
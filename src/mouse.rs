@@ -0,0 +1,211 @@
+use crate::term::BufWrite as _;
+
+/// A mouse button.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MouseButton {
+    /// The left (primary) button.
+    Left,
+    /// The middle button, which is often the scroll wheel itself.
+    Middle,
+    /// The right (secondary) button.
+    Right,
+    /// Scrolling the wheel up. The wheel only sends press events.
+    WheelUp,
+    /// Scrolling the wheel down.
+    WheelDown,
+    /// Scrolling the wheel (or tilting it) to the left.
+    WheelLeft,
+    /// Scrolling the wheel (or tilting it) to the right.
+    WheelRight,
+}
+
+impl MouseButton {
+    fn is_wheel(self) -> bool {
+        matches!(
+            self,
+            Self::WheelUp
+                | Self::WheelDown
+                | Self::WheelLeft
+                | Self::WheelRight
+        )
+    }
+
+    fn code(self) -> u16 {
+        match self {
+            Self::Left => 0,
+            Self::Middle => 1,
+            Self::Right => 2,
+            Self::WheelUp => 64,
+            Self::WheelDown => 65,
+            Self::WheelLeft => 66,
+            Self::WheelRight => 67,
+        }
+    }
+}
+
+/// The kind of a mouse event.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MouseEventKind {
+    /// A button was pressed, or the wheel was scrolled.
+    Press,
+    /// A button was released.
+    Release,
+    /// The mouse moved to a different cell (or pixel, with the
+    /// `SgrPixels` encoding).
+    Motion,
+}
+
+/// A mouse event to be sent to the application running in the terminal,
+/// to be encoded with `Screen::mouse_event`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    /// The button which was pressed or released, or which is held down
+    /// during a motion event. This should only be `None` for motion events
+    /// with no buttons held down.
+    pub button: Option<MouseButton>,
+    /// The cell the event happened in, as zero-based (row, col). When the
+    /// `SgrPixels` encoding is active, this should be the position in
+    /// pixels instead.
+    pub position: (u16, u16),
+    /// Only shift, alt, and ctrl are reported to the application.
+    pub modifiers: crate::Modifiers,
+}
+
+impl MouseEvent {
+    /// Creates a mouse event with no modifiers.
+    #[must_use]
+    pub fn new(
+        kind: MouseEventKind,
+        button: Option<MouseButton>,
+        position: (u16, u16),
+    ) -> Self {
+        Self {
+            kind,
+            button,
+            position,
+            modifiers: crate::Modifiers::default(),
+        }
+    }
+
+    pub(crate) fn write_buf(
+        &self,
+        buf: &mut Vec<u8>,
+        mode: crate::MouseProtocolMode,
+        encoding: crate::MouseProtocolEncoding,
+    ) {
+        if !self.is_reported(mode) {
+            return;
+        }
+
+        let mut code = match (self.kind, self.button) {
+            (MouseEventKind::Release, _)
+                if !matches!(
+                    encoding,
+                    crate::MouseProtocolEncoding::Sgr
+                        | crate::MouseProtocolEncoding::SgrPixels
+                ) =>
+            {
+                // only the sgr encoding reports which button was released
+                3
+            }
+            (_, Some(button)) => button.code(),
+            (_, None) => 3,
+        };
+        if self.kind == MouseEventKind::Motion {
+            code += 32;
+        }
+        // x10 compatibility mode doesn't report modifiers
+        if mode != crate::MouseProtocolMode::Press {
+            let mods = self.modifiers;
+            code += 4 * u16::from(mods.shift)
+                + 8 * u16::from(mods.alt)
+                + 16 * u16::from(mods.ctrl);
+        }
+
+        let (row, col) = self.position;
+        match encoding {
+            crate::MouseProtocolEncoding::Default => {
+                // each value is sent as a single byte offset by 32
+                let (Ok(code), Ok(col), Ok(row)) = (
+                    u8::try_from(code + 32),
+                    u8::try_from(u32::from(col) + 33),
+                    u8::try_from(u32::from(row) + 33),
+                ) else {
+                    return;
+                };
+                crate::term::MouseReport::new(code, col, row).write_buf(buf);
+            }
+            crate::MouseProtocolEncoding::Utf8 => {
+                // each value is sent as a utf-8 encoded character offset by
+                // 32, limited to what fits in two bytes
+                let (Some(code), Some(col), Some(row)) = (
+                    utf8_mouse_char(u32::from(code) + 32),
+                    utf8_mouse_char(u32::from(col) + 33),
+                    utf8_mouse_char(u32::from(row) + 33),
+                ) else {
+                    return;
+                };
+                crate::term::Utf8MouseReport::new(code, col, row)
+                    .write_buf(buf);
+            }
+            crate::MouseProtocolEncoding::Urxvt => {
+                crate::term::UrxvtMouseReport::new(
+                    code + 32,
+                    u32::from(col) + 1,
+                    u32::from(row) + 1,
+                )
+                .write_buf(buf);
+            }
+            crate::MouseProtocolEncoding::Sgr
+            | crate::MouseProtocolEncoding::SgrPixels => {
+                crate::term::SgrMouseReport::new(
+                    code,
+                    u32::from(col) + 1,
+                    u32::from(row) + 1,
+                    self.kind == MouseEventKind::Release,
+                )
+                .write_buf(buf);
+            }
+        }
+    }
+
+    fn is_reported(&self, mode: crate::MouseProtocolMode) -> bool {
+        let wheel = self.button.is_some_and(MouseButton::is_wheel);
+        match self.kind {
+            // locator reports are only sent when requested, and highlight
+            // tracking needs the application to answer each press before
+            // the mouse can move on, neither of which is supported
+            MouseEventKind::Press => !matches!(
+                mode,
+                crate::MouseProtocolMode::None
+                    | crate::MouseProtocolMode::Highlight
+                    | crate::MouseProtocolMode::DecLocator
+            ),
+            // the wheel has no release events
+            MouseEventKind::Release => {
+                !wheel
+                    && matches!(
+                        mode,
+                        crate::MouseProtocolMode::PressRelease
+                            | crate::MouseProtocolMode::ButtonMotion
+                            | crate::MouseProtocolMode::AnyMotion
+                    )
+            }
+            MouseEventKind::Motion => match mode {
+                crate::MouseProtocolMode::ButtonMotion => {
+                    self.button.is_some()
+                }
+                crate::MouseProtocolMode::AnyMotion => true,
+                _ => false,
+            },
+        }
+    }
+}
+
+fn utf8_mouse_char(value: u32) -> Option<char> {
+    if value >= 0x800 {
+        return None;
+    }
+    char::from_u32(value)
+}
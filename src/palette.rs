@@ -0,0 +1,159 @@
+use crate::term::BufWrite as _;
+
+/// The colors which have been overridden by the running application via
+/// OSC 4, 10, 11, and 12.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Palette {
+    pub colors: std::collections::BTreeMap<u8, (u8, u8, u8)>,
+    pub foreground: Option<(u8, u8, u8)>,
+    pub background: Option<(u8, u8, u8)>,
+    pub cursor: Option<(u8, u8, u8)>,
+}
+
+impl Palette {
+    pub fn dynamic(&self, code: u16) -> Option<(u8, u8, u8)> {
+        match code {
+            10 => self.foreground,
+            11 => self.background,
+            12 => self.cursor,
+            _ => None,
+        }
+    }
+
+    pub fn set_dynamic(&mut self, code: u16, rgb: Option<(u8, u8, u8)>) {
+        match code {
+            10 => self.foreground = rgb,
+            11 => self.background = rgb,
+            12 => self.cursor = rgb,
+            _ => {}
+        }
+    }
+
+    pub fn write_escape_code_diff(
+        &self,
+        contents: &mut Vec<u8>,
+        other: &Self,
+    ) {
+        if self.colors.is_empty() && !other.colors.is_empty() {
+            crate::term::ResetColor::new(104, None).write_buf(contents);
+        } else {
+            for idx in other.colors.keys() {
+                if !self.colors.contains_key(idx) {
+                    crate::term::ResetColor::new(104, Some(*idx))
+                        .write_buf(contents);
+                }
+            }
+        }
+        for (idx, rgb) in &self.colors {
+            if other.colors.get(idx) != Some(rgb) {
+                crate::term::OscColor::new(4, Some(*idx), *rgb, true)
+                    .write_buf(contents);
+            }
+        }
+
+        for code in 10..=12 {
+            let rgb = self.dynamic(code);
+            if rgb == other.dynamic(code) {
+                continue;
+            }
+            if let Some(rgb) = rgb {
+                crate::term::OscColor::new(code, None, rgb, true)
+                    .write_buf(contents);
+            } else {
+                crate::term::ResetColor::new(code + 100, None)
+                    .write_buf(contents);
+            }
+        }
+    }
+}
+
+/// Returns the color that xterm uses for the given palette index by
+/// default.
+pub fn default_color(idx: u8) -> (u8, u8, u8) {
+    const BASE: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match idx {
+        0..=15 => BASE[usize::from(idx)],
+        16..=231 => {
+            let idx = usize::from(idx - 16);
+            (CUBE[idx / 36], CUBE[(idx / 6) % 6], CUBE[idx % 6])
+        }
+        _ => {
+            let level = 8 + (idx - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Parses a color specification in one of the formats understood by
+/// `XParseColor` (`rgb:r/g/b` with one to four hex digits per component,
+/// or `#rgb` with one to four hex digits per component). Color names are
+/// not supported.
+pub fn parse_color_spec(spec: &[u8]) -> Option<(u8, u8, u8)> {
+    if let Some(spec) = spec.strip_prefix(b"rgb:") {
+        let mut components = spec.split(|&c| c == b'/').map(|component| {
+            if component.is_empty() || component.len() > 4 {
+                return None;
+            }
+            let value = parse_hex(component)?;
+            // scale the component from however many digits were given
+            // into the range of a u8, the way XParseColor does
+            let max = (1u32 << (4 * component.len())) - 1;
+            u8::try_from((value * 255 + max / 2) / max).ok()
+        });
+        let rgb = (
+            components.next()??,
+            components.next()??,
+            components.next()??,
+        );
+        return components.next().is_none().then_some(rgb);
+    }
+
+    if let Some(spec) = spec.strip_prefix(b"#") {
+        if spec.is_empty() || spec.len() % 3 != 0 || spec.len() > 12 {
+            return None;
+        }
+        let len = spec.len() / 3;
+        let mut components = spec.chunks(len).map(|component| {
+            // the # syntax specifies the high order bits of each component
+            let value = parse_hex(component)?;
+            let value = if len == 1 {
+                value << 4
+            } else {
+                value >> (4 * (len - 2))
+            };
+            u8::try_from(value).ok()
+        });
+        return Some((
+            components.next()??,
+            components.next()??,
+            components.next()??,
+        ));
+    }
+
+    None
+}
+
+fn parse_hex(s: &[u8]) -> Option<u32> {
+    s.iter().try_fold(0, |value, &c| {
+        Some(value * 16 + char::from(c).to_digit(16)?)
+    })
+}
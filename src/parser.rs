@@ -1,8 +1,54 @@
+const DEFAULT_MAX_CLIPBOARD_LEN: usize = 1024 * 1024;
+const DEFAULT_SYNCHRONIZED_OUTPUT_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(1);
+const DEFAULT_MAX_SYNCHRONIZED_OUTPUT_LEN: usize = 1024 * 1024;
+
+// a VT100 with advanced video option, which is what most terminal
+// multiplexers claim to be
+const DEFAULT_PRIMARY_DEVICE_ATTRIBUTES: &[u16] = &[1, 2];
+
+// the color support which the screen itself provides
+const DEFAULT_TERMINAL_CAPABILITIES: &[(&str, &str)] =
+    &[("Co", "256"), ("colors", "256"), ("RGB", "")];
+
+pub struct Config {
+    pub max_clipboard_len: usize,
+    pub primary_device_attributes: Vec<u16>,
+    pub secondary_device_attributes: (u16, u16),
+    pub synchronized_output_timeout: std::time::Duration,
+    pub max_synchronized_output_len: usize,
+    pub terminal_capabilities: std::collections::HashMap<String, String>,
+    pub default_dynamic_colors: [Option<(u8, u8, u8)>; 3],
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_clipboard_len: DEFAULT_MAX_CLIPBOARD_LEN,
+            primary_device_attributes: DEFAULT_PRIMARY_DEVICE_ATTRIBUTES
+                .to_vec(),
+            secondary_device_attributes: (0, 0),
+            synchronized_output_timeout: DEFAULT_SYNCHRONIZED_OUTPUT_TIMEOUT,
+            max_synchronized_output_len: DEFAULT_MAX_SYNCHRONIZED_OUTPUT_LEN,
+            terminal_capabilities: DEFAULT_TERMINAL_CAPABILITIES
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            default_dynamic_colors: [None; 3],
+        }
+    }
+}
+
 /// A parser for terminal output which produces an in-memory representation of
 /// the terminal contents.
+#[allow(clippy::struct_field_names)]
 pub struct Parser {
     parser: vte::Parser,
     screen: crate::perform::WrappedScreen,
+    config: Config,
+    // the start time of the synchronized update in progress, as reported by
+    // the screen, and the number of bytes processed while it was in progress
+    synchronized_output: Option<(std::time::Instant, usize)>,
 }
 
 impl Parser {
@@ -12,17 +58,112 @@ impl Parser {
     pub fn new(rows: u16, cols: u16, scrollback_len: usize) -> Self {
         Self {
             parser: vte::Parser::new(),
-            screen: crate::perform::WrappedScreen(crate::Screen::new(
-                crate::grid::Size { rows, cols },
-                scrollback_len,
-            )),
+            screen: crate::perform::WrappedScreen(
+                crate::Screen::new(
+                    crate::grid::Size { rows, cols },
+                    scrollback_len,
+                ),
+                None,
+            ),
+            config: Config::default(),
+            synchronized_output: None,
         }
     }
 
+    /// Sets the maximum size in bytes of clipboard contents which will be
+    /// passed to or from `Callbacks` via OSC 52. Larger payloads are
+    /// ignored. Defaults to 1MiB.
+    pub fn set_max_clipboard_len(&mut self, len: usize) {
+        self.config.max_clipboard_len = len;
+    }
+
+    /// Sets the attributes reported in response to a primary device
+    /// attributes query (`CSI c`), as the parameters following the `?` in
+    /// the response. Defaults to `1;2` (a VT100 with advanced video option).
+    pub fn set_primary_device_attributes(&mut self, attrs: &[u16]) {
+        self.config.primary_device_attributes = attrs.to_vec();
+    }
+
+    /// Sets the terminal type and firmware version reported in response to
+    /// a secondary device attributes query (`CSI > c`). Defaults to `0;0`.
+    pub fn set_secondary_device_attributes(
+        &mut self,
+        terminal_type: u16,
+        version: u16,
+    ) {
+        self.config.secondary_device_attributes = (terminal_type, version);
+    }
+
+    /// Sets the terminfo capabilities reported in response to an XTGETTCAP
+    /// query (`DCS + q`), as pairs of capability names and values.
+    /// Capabilities with an empty value are reported as boolean
+    /// capabilities. Defaults to the 256 color and direct color support
+    /// provided by the screen (`Co`, `colors`, and `RGB`).
+    pub fn set_terminal_capabilities(
+        &mut self,
+        capabilities: &[(&str, &str)],
+    ) {
+        self.config.terminal_capabilities = capabilities
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+    }
+
+    /// Sets the colors reported in response to queries for the default
+    /// foreground, background, and cursor colors (`OSC 10`, `OSC 11`, and
+    /// `OSC 12` with a `?` specification) when the application hasn't set
+    /// them itself. Queries for colors which are `None` aren't answered
+    /// unless the application has set them. Defaults to `None` for all
+    /// three, since the colors used by the real terminal aren't known.
+    pub fn set_default_dynamic_colors(
+        &mut self,
+        foreground: Option<(u8, u8, u8)>,
+        background: Option<(u8, u8, u8)>,
+        cursor: Option<(u8, u8, u8)>,
+    ) {
+        self.config.default_dynamic_colors = [foreground, background, cursor];
+    }
+
+    /// Sets the limits on synchronized updates (`\e[?2026h`). If the
+    /// application doesn't finish a synchronized update within `timeout`,
+    /// or writes more than `max_len` bytes during it, the update is ended
+    /// as if the application had finished it, so that a misbehaving
+    /// application can't prevent the screen from being rendered forever.
+    /// These limits are checked after each call to `process` or
+    /// `process_cb`, and by `end_expired_synchronized_output`. Defaults to
+    /// one second and 1MiB.
+    pub fn set_synchronized_output_limits(
+        &mut self,
+        timeout: std::time::Duration,
+        max_len: usize,
+    ) {
+        self.config.synchronized_output_timeout = timeout;
+        self.config.max_synchronized_output_len = max_len;
+    }
+
+    /// Ends the synchronized update in progress if it has exceeded the
+    /// timeout set via `set_synchronized_output_limits`, and returns whether
+    /// it did so. This is useful for checking the timeout when the
+    /// application hasn't written anything for a while.
+    pub fn end_expired_synchronized_output(&mut self) -> bool {
+        self.update_synchronized_output()
+    }
+
     /// Processes the contents of the given byte string, and updates the
     /// in-memory terminal state.
     pub fn process(&mut self, bytes: &[u8]) {
-        self.parser.advance(&mut self.screen, bytes);
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            self.screen.1 = self.screen.0.synchronized_output_start();
+            let len = self
+                .parser
+                .advance_until_terminated(&mut self.screen, bytes);
+            self.count_synchronized_output(len);
+            bytes = &bytes[len..];
+        }
+        // replies to queries are only delivered via `Callbacks::reply`
+        self.screen.0.take_replies();
+        self.update_synchronized_output();
     }
 
     /// Processes the contents of the given byte string, and updates the
@@ -33,11 +174,22 @@ impl Parser {
         bytes: &[u8],
         callbacks: &mut impl crate::callbacks::Callbacks,
     ) {
-        let mut screen = crate::perform::WrappedScreenWithCallbacks::new(
-            &mut self.screen,
-            callbacks,
-        );
-        self.parser.advance(&mut screen, bytes);
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            self.screen.1 = self.screen.0.synchronized_output_start();
+            let mut screen = crate::perform::WrappedScreenWithCallbacks::new(
+                &mut self.screen,
+                callbacks,
+                &self.config,
+            );
+            let len =
+                self.parser.advance_until_terminated(&mut screen, bytes);
+            self.count_synchronized_output(len);
+            bytes = &bytes[len..];
+        }
+        if self.update_synchronized_output() {
+            callbacks.synchronized_output_complete(&mut self.screen.0);
+        }
     }
 
     /// Returns a reference to a `Screen` object containing the terminal
@@ -53,6 +205,46 @@ impl Parser {
     pub fn screen_mut(&mut self) -> &mut crate::Screen {
         &mut self.screen.0
     }
+
+    // parsing stops whenever a synchronized update starts or ends, so the
+    // bytes which were just parsed all belong to the update (if any) which
+    // was in progress when parsing started
+    fn count_synchronized_output(&mut self, len: usize) {
+        let Some(start) = self.screen.1 else {
+            return;
+        };
+        match &mut self.synchronized_output {
+            Some((prev_start, total_len)) if *prev_start == start => {
+                *total_len += len;
+            }
+            _ => self.synchronized_output = Some((start, len)),
+        }
+    }
+
+    // returns true if the synchronized update in progress was ended because
+    // it exceeded its limits
+    fn update_synchronized_output(&mut self) -> bool {
+        let Some(start) = self.screen.0.synchronized_output_start() else {
+            self.synchronized_output = None;
+            return false;
+        };
+        // the screen's start time changes if the previous update ended and a
+        // new one started without any bytes being parsed during it
+        let total_len = match self.synchronized_output {
+            Some((prev_start, total_len)) if prev_start == start => total_len,
+            _ => 0,
+        };
+        if start.elapsed() < self.config.synchronized_output_timeout
+            && total_len <= self.config.max_synchronized_output_len
+        {
+            return false;
+        }
+
+        log::debug!("synchronized update exceeded its limits");
+        self.screen.0.end_synchronized_output();
+        self.synchronized_output = None;
+        true
+    }
 }
 
 impl Default for Parser {
@@ -0,0 +1,64 @@
+const BRACKETED_PASTE_START: &str = "\x1b[200~";
+const BRACKETED_PASTE_END: &str = "\x1b[201~";
+const BRACKETED_PASTE_END_C1: &str = "\u{9b}201~";
+
+/// Text to be pasted into the application running in the terminal, to be
+/// encoded with `Screen::paste`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Paste<'a> {
+    pub text: &'a str,
+    /// Whether to remove control characters (other than tabs and newlines)
+    /// from the text when bracketed paste is disabled. Without bracketed
+    /// paste, the application can't tell pasted text apart from typed
+    /// text, so pasted control characters would act like the user typing
+    /// them. Defaults to `true`.
+    pub strip_controls: bool,
+}
+
+impl<'a> Paste<'a> {
+    /// Creates a paste of the given text, which strips control characters
+    /// when bracketed paste is disabled.
+    #[must_use]
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            strip_controls: true,
+        }
+    }
+
+    pub(crate) fn write_buf(&self, buf: &mut Vec<u8>, bracketed_paste: bool) {
+        // newlines are sent the same way as the enter key
+        let mut text = self.text.replace("\r\n", "\r").replace('\n', "\r");
+
+        if bracketed_paste {
+            buf.extend_from_slice(BRACKETED_PASTE_START.as_bytes());
+            buf.extend_from_slice(strip_end_markers(&text).as_bytes());
+            buf.extend_from_slice(BRACKETED_PASTE_END.as_bytes());
+        } else {
+            if self.strip_controls {
+                text.retain(|c| !c.is_control() || c == '\t' || c == '\r');
+            }
+            buf.extend_from_slice(text.as_bytes());
+        }
+    }
+}
+
+// removes anything which the application could interpret as the end of the
+// paste. markers are removed as soon as they appear at the end of the
+// output, so that text joined together by removing a marker can't form a
+// new one.
+fn strip_end_markers(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        stripped.push(c);
+        if c != '~' {
+            continue;
+        }
+        for marker in [BRACKETED_PASTE_END, BRACKETED_PASTE_END_C1] {
+            if stripped.ends_with(marker) {
+                stripped.truncate(stripped.len() - marker.len());
+            }
+        }
+    }
+    stripped
+}
@@ -1,4 +1,12 @@
-pub struct WrappedScreen(pub crate::Screen);
+use crate::term::BufWrite as _;
+
+pub struct WrappedScreen(
+    pub crate::Screen,
+    // the start time of the synchronized update which was in progress (if
+    // any) when parsing started, so that parsing can be stopped whenever a
+    // synchronized update starts or ends
+    pub Option<std::time::Instant>,
+);
 
 impl vte::Perform for WrappedScreen {
     fn print(&mut self, c: char) {
@@ -16,9 +24,10 @@ impl vte::Perform for WrappedScreen {
             11 => self.0.vt(),
             12 => self.0.ff(),
             13 => self.0.cr(),
-            // we don't implement shift in/out alternate character sets, but
-            // it shouldn't count as an "error"
-            7 | 14 | 15 => {}
+            14 => self.0.so(),
+            15 => self.0.si(),
+            // we don't implement bells, but it shouldn't count as an "error"
+            7 => {}
             _ => {
                 log::debug!("unhandled control character: {b}");
             }
@@ -26,23 +35,30 @@ impl vte::Perform for WrappedScreen {
     }
 
     fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, b: u8) {
-        intermediates.first().map_or_else(
-            || match b {
+        match intermediates.first() {
+            None => match b {
                 b'7' => self.0.decsc(),
                 b'8' => self.0.decrc(),
                 b'=' => self.0.deckpam(),
                 b'>' => self.0.deckpnm(),
+                b'H' => self.0.hts(),
                 b'M' => self.0.ri(),
+                b'N' => self.0.ss2(),
+                b'O' => self.0.ss3(),
                 b'c' => self.0.ris(),
                 b'g' => {}
                 _ => {
                     log::debug!("unhandled escape code: ESC {b}");
                 }
             },
-            |i| {
+            Some(b'(') => self.0.scs(0, b),
+            Some(b')') => self.0.scs(1, b),
+            Some(b'*') => self.0.scs(2, b),
+            Some(b'+') => self.0.scs(3, b),
+            Some(i) => {
                 log::debug!("unhandled escape code: ESC {i} {b}");
-            },
-        );
+            }
+        }
     }
 
     fn csi_dispatch(
@@ -63,6 +79,7 @@ impl vte::Perform for WrappedScreen {
                 'F' => self.0.cpl(canonicalize_params_1(params, 1)),
                 'G' => self.0.cha(canonicalize_params_1(params, 1)),
                 'H' => self.0.cup(canonicalize_params_2(params, 1, 1)),
+                'I' => self.0.cht(canonicalize_params_1(params, 1)),
                 'J' => self.0.ed(canonicalize_params_1(params, 0)),
                 'K' => self.0.el(canonicalize_params_1(params, 0)),
                 'L' => self.0.il(canonicalize_params_1(params, 1)),
@@ -71,10 +88,16 @@ impl vte::Perform for WrappedScreen {
                 'S' => self.0.su(canonicalize_params_1(params, 1)),
                 'T' => self.0.sd(canonicalize_params_1(params, 1)),
                 'X' => self.0.ech(canonicalize_params_1(params, 1)),
+                'Z' => self.0.cbt(canonicalize_params_1(params, 1)),
                 'd' => self.0.vpa(canonicalize_params_1(params, 1)),
+                'g' => self.0.tbc(canonicalize_params_1(params, 0)),
                 'h' => self.0.sm(params),
                 'l' => self.0.rm(params),
+                'c' => {
+                    // device attributes are only reported via `Callbacks`
+                }
                 'm' => self.0.sgr(params),
+                'n' => self.0.dsr(params),
                 'r' => self.0.decstbm(canonicalize_params_decstbm(
                     params,
                     self.0.grid().size(),
@@ -95,6 +118,11 @@ impl vte::Perform for WrappedScreen {
                 'K' => self.0.decsel(canonicalize_params_1(params, 0)),
                 'h' => self.0.decset(params),
                 'l' => self.0.decrst(params),
+                'n' => self.0.decdsr(params),
+                'u' => self.0.query_kitty_keyboard_flags(),
+                'p' if intermediates.get(1) == Some(&b'$') => {
+                    self.0.decrqm(canonicalize_params_1(params, 0));
+                }
                 _ => {
                     if log::log_enabled!(log::Level::Debug) {
                         log::debug!(
@@ -105,6 +133,27 @@ impl vte::Perform for WrappedScreen {
                     }
                 }
             },
+            Some(b' ') if c == 'q' => {
+                self.0.decscusr(canonicalize_params_1(params, 0));
+            }
+            Some(b'\'') if c == 'z' => {
+                self.0.decelr(canonicalize_params_1(params, 0));
+            }
+            Some(b'$') if c == 'p' => {
+                self.0.rqm(canonicalize_params_1(params, 0));
+            }
+            Some(b'>') if c == 'c' => {
+                // device attributes are only reported via `Callbacks`
+            }
+            Some(b'>') if c == 'u' => self
+                .0
+                .push_kitty_keyboard_flags(canonicalize_params_1(params, 0)),
+            Some(b'<') if c == 'u' => self
+                .0
+                .pop_kitty_keyboard_flags(canonicalize_params_1(params, 1)),
+            Some(b'=') if c == 'u' => self.0.set_kitty_keyboard_flags(
+                canonicalize_params_2(params, 0, 1),
+            ),
             Some(i) => {
                 if log::log_enabled!(log::Level::Debug) {
                     log::debug!(
@@ -118,11 +167,55 @@ impl vte::Perform for WrappedScreen {
         }
     }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]], _bel_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], bel_terminated: bool) {
         match (params.get(0), params.get(1)) {
             (Some(&b"0"), Some(s)) => self.0.osc0(s),
             (Some(&b"1"), Some(s)) => self.0.osc1(s),
             (Some(&b"2"), Some(s)) => self.0.osc2(s),
+            (Some(&b"7"), Some(_)) => {
+                // the uri itself can contain semicolons
+                let uri = params[1..].join(&b';');
+                self.0.osc7(&uri);
+            }
+            (Some(&b"133"), _) => self.0.osc133(&params[1..]),
+            (Some(&b"8"), Some(link_params)) => {
+                // the uri itself can contain semicolons
+                let uri = params[2..].join(&b';');
+                self.0.osc8(link_params, &uri);
+            }
+            (Some(&b"4"), Some(_)) => {
+                self.0.osc4(&params[1..], bel_terminated);
+            }
+            (Some(&b"10"), Some(_)) => {
+                self.0.osc_dynamic_color(
+                    10,
+                    &params[1..],
+                    bel_terminated,
+                    &[None; 3],
+                );
+            }
+            (Some(&b"11"), Some(_)) => {
+                self.0.osc_dynamic_color(
+                    11,
+                    &params[1..],
+                    bel_terminated,
+                    &[None; 3],
+                );
+            }
+            (Some(&b"12"), Some(_)) => {
+                self.0.osc_dynamic_color(
+                    12,
+                    &params[1..],
+                    bel_terminated,
+                    &[None; 3],
+                );
+            }
+            (Some(&b"104"), _) => self.0.osc104(&params[1..]),
+            (Some(&b"110"), _) => self.0.osc_reset_dynamic_color(110),
+            (Some(&b"111"), _) => self.0.osc_reset_dynamic_color(111),
+            (Some(&b"112"), _) => self.0.osc_reset_dynamic_color(112),
+            // clipboard access is only handled via callbacks
+            (Some(&b"52"), Some(_)) => {}
             _ => {
                 if log::log_enabled!(log::Level::Debug) {
                     log::debug!(
@@ -141,7 +234,13 @@ impl vte::Perform for WrappedScreen {
         _ignore: bool,
         action: char,
     ) {
-        if log::log_enabled!(log::Level::Debug) {
+        let request = match (intermediates, action) {
+            (b"$", 'q') => Some(crate::screen::DcsRequest::Decrqss),
+            (b"+", 'q') => Some(crate::screen::DcsRequest::Xtgettcap),
+            _ => None,
+        };
+        self.0.dcs_hook(request);
+        if request.is_none() && log::log_enabled!(log::Level::Debug) {
             intermediates.first().map_or_else(
                 || {
                     log::debug!(
@@ -161,6 +260,19 @@ impl vte::Perform for WrappedScreen {
             );
         }
     }
+
+    fn put(&mut self, b: u8) {
+        self.0.dcs_put(b);
+    }
+
+    fn unhook(&mut self) {
+        // dcs requests are only answered via `Callbacks`
+        self.0.take_dcs();
+    }
+
+    fn terminated(&self) -> bool {
+        self.0.synchronized_output_start() != self.1
+    }
 }
 
 fn canonicalize_params_1(params: &vte::Params, default: u16) -> u16 {
@@ -226,14 +338,104 @@ fn osc_param_str(params: &[&[u8]]) -> String {
 pub struct WrappedScreenWithCallbacks<'a, T: crate::callbacks::Callbacks> {
     screen: &'a mut crate::perform::WrappedScreen,
     callbacks: &'a mut T,
+    config: &'a crate::parser::Config,
 }
 
 impl<'a, T: crate::callbacks::Callbacks> WrappedScreenWithCallbacks<'a, T> {
     pub fn new(
         screen: &'a mut crate::perform::WrappedScreen,
         callbacks: &'a mut T,
+        config: &'a crate::parser::Config,
     ) -> Self {
-        Self { screen, callbacks }
+        Self {
+            screen,
+            callbacks,
+            config,
+        }
+    }
+
+    // OSC 52
+    fn clipboard(
+        &mut self,
+        selection: &[u8],
+        data: &[u8],
+        bel_terminated: bool,
+    ) {
+        // xterm treats an empty selection parameter as "s0"
+        let selection = if selection.is_empty() {
+            &b"s0"[..]
+        } else {
+            selection
+        };
+        if data == b"?" {
+            let Some(data) = self
+                .callbacks
+                .paste_from_clipboard(&mut self.screen.0, selection)
+            else {
+                return;
+            };
+            if data.len() > self.config.max_clipboard_len {
+                log::debug!(
+                    "clipboard contents too large to send: {} bytes",
+                    data.len()
+                );
+                return;
+            }
+            let mut reply = vec![];
+            crate::term::Clipboard::new(
+                selection,
+                &crate::base64::encode(&data),
+                bel_terminated,
+            )
+            .write_buf(&mut reply);
+            self.callbacks.reply(&mut self.screen.0, &reply);
+        } else if let Some(data) = crate::base64::decode(data) {
+            if data.len() > self.config.max_clipboard_len {
+                log::debug!(
+                    "clipboard contents too large to copy: {} bytes",
+                    data.len()
+                );
+                return;
+            }
+            self.callbacks.copy_to_clipboard(
+                &mut self.screen.0,
+                selection,
+                &data,
+            );
+        } else {
+            log::debug!("invalid base64 data for OSC 52");
+        }
+    }
+
+    // CSI c, CSI > c
+    fn device_attributes(&mut self, params: &vte::Params, secondary: bool) {
+        if !matches!(
+            params.iter().next().and_then(|x| x.first().copied()),
+            None | Some(0)
+        ) {
+            return;
+        }
+        if secondary {
+            self.screen
+                .0
+                .secondary_da(self.config.secondary_device_attributes);
+        } else {
+            self.screen.0.da(&self.config.primary_device_attributes);
+        }
+    }
+
+    fn check_synchronized_output(&mut self, prev_synchronized_output: bool) {
+        if prev_synchronized_output && !self.screen.0.synchronized_output() {
+            self.callbacks
+                .synchronized_output_complete(&mut self.screen.0);
+        }
+    }
+
+    fn send_replies(&mut self) {
+        let replies = self.screen.0.take_replies();
+        if !replies.is_empty() {
+            self.callbacks.reply(&mut self.screen.0, &replies);
+        }
     }
 }
 
@@ -262,7 +464,9 @@ impl<'a, T: crate::callbacks::Callbacks> vte::Perform
         if intermediates.is_empty() && b == b'g' {
             self.callbacks.visual_bell(&mut self.screen.0);
         }
+        let prev_synchronized_output = self.screen.0.synchronized_output();
         self.screen.esc_dispatch(intermediates, ignore, b);
+        self.check_synchronized_output(prev_synchronized_output);
     }
 
     fn csi_dispatch(
@@ -286,11 +490,59 @@ impl<'a, T: crate::callbacks::Callbacks> vte::Perform
                 self.callbacks.resize(&mut self.screen.0, (rows, cols));
             }
         }
+        if c == 'c' {
+            match intermediates {
+                [] => self.device_attributes(params, false),
+                [b'>'] => self.device_attributes(params, true),
+                _ => {}
+            }
+        }
+        let prev_synchronized_output = self.screen.0.synchronized_output();
         self.screen.csi_dispatch(params, intermediates, ignore, c);
+        self.check_synchronized_output(prev_synchronized_output);
+        self.send_replies();
     }
 
     fn osc_dispatch(&mut self, params: &[&[u8]], bel_terminated: bool) {
-        self.screen.osc_dispatch(params, bel_terminated);
+        if let (Some(&b"52"), Some(selection), Some(data)) =
+            (params.first(), params.get(1), params.get(2))
+        {
+            self.clipboard(selection, data, bel_terminated);
+        }
+        let prev_working_directory = (params.first() == Some(&&b"7"[..]))
+            .then(|| {
+                let screen = &self.screen.0;
+                (
+                    screen.working_directory_host().map(str::to_string),
+                    screen.working_directory().map(str::to_string),
+                )
+            });
+        let dynamic_color = match params.first() {
+            Some(&b"10") => Some(10),
+            Some(&b"11") => Some(11),
+            Some(&b"12") => Some(12),
+            _ => None,
+        };
+        if let (Some(code), Some(_)) = (dynamic_color, params.get(1)) {
+            // queries for colors which haven't been set can only be answered
+            // with the configured defaults
+            self.screen.0.osc_dynamic_color(
+                code,
+                &params[1..],
+                bel_terminated,
+                &self.config.default_dynamic_colors,
+            );
+        } else {
+            self.screen.osc_dispatch(params, bel_terminated);
+        }
+        if let Some((host, path)) = prev_working_directory {
+            if self.screen.0.working_directory_host() != host.as_deref()
+                || self.screen.0.working_directory() != path.as_deref()
+            {
+                self.callbacks.working_directory(&mut self.screen.0);
+            }
+        }
+        self.send_replies();
     }
 
     fn hook(
@@ -302,4 +554,23 @@ impl<'a, T: crate::callbacks::Callbacks> vte::Perform
     ) {
         self.screen.hook(params, intermediates, ignore, action);
     }
+
+    fn put(&mut self, b: u8) {
+        self.screen.put(b);
+    }
+
+    fn unhook(&mut self) {
+        if let Some((request, data)) = self.screen.0.take_dcs() {
+            self.screen.0.dcs(
+                request,
+                &data,
+                &self.config.terminal_capabilities,
+            );
+            self.send_replies();
+        }
+    }
+
+    fn terminated(&self) -> bool {
+        self.screen.terminated()
+    }
 }
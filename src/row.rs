@@ -4,6 +4,8 @@ use crate::term::BufWrite as _;
 pub struct Row {
     cells: Vec<crate::Cell>,
     wrapped: bool,
+    // semantic prompt marks, sorted by column, with at most one per column
+    marks: Vec<(u16, crate::command::Mark)>,
 }
 
 impl Row {
@@ -11,10 +13,11 @@ impl Row {
         Self {
             cells: vec![crate::Cell::new(); usize::from(cols)],
             wrapped: false,
+            marks: vec![],
         }
     }
 
-    fn cols(&self) -> u16 {
+    pub fn cols(&self) -> u16 {
         self.cells
             .len()
             .try_into()
@@ -27,9 +30,10 @@ impl Row {
             cell.clear(attrs);
         }
         self.wrapped = false;
+        self.marks.clear();
     }
 
-    fn cells(&self) -> impl Iterator<Item = &crate::Cell> {
+    pub fn cells(&self) -> impl Iterator<Item = &crate::Cell> {
         self.cells.iter()
     }
 
@@ -44,12 +48,23 @@ impl Row {
     pub fn insert(&mut self, i: u16, cell: crate::Cell) {
         self.cells.insert(usize::from(i), cell);
         self.wrapped = false;
+        for (col, _) in &mut self.marks {
+            if *col >= i {
+                *col += 1;
+            }
+        }
     }
 
     pub fn remove(&mut self, i: u16) {
         self.clear_wide(i);
         self.cells.remove(usize::from(i));
         self.wrapped = false;
+        self.marks.retain(|(col, _)| *col != i);
+        for (col, _) in &mut self.marks {
+            if *col > i {
+                *col -= 1;
+            }
+        }
     }
 
     pub fn erase(&mut self, i: u16, attrs: crate::attrs::Attrs) {
@@ -59,11 +74,14 @@ impl Row {
         if i == self.cols() - if wide { 2 } else { 1 } {
             self.wrapped = false;
         }
+        // erased cells don't belong to a semantic zone anymore
+        self.marks.retain(|(col, _)| *col != i);
     }
 
     pub fn truncate(&mut self, len: u16) {
         self.cells.truncate(usize::from(len));
         self.wrapped = false;
+        self.marks.retain(|(col, _)| *col < len);
         let last_cell = &mut self.cells[usize::from(len) - 1];
         if last_cell.is_wide() {
             last_cell.clear(*last_cell.attrs());
@@ -73,6 +91,28 @@ impl Row {
     pub fn resize(&mut self, len: u16, cell: crate::Cell) {
         self.cells.resize(usize::from(len), cell);
         self.wrapped = false;
+        self.marks.retain(|(col, _)| *col < len);
+    }
+
+    pub fn marks(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (u16, crate::command::Mark)> + '_
+    {
+        self.marks.iter().copied()
+    }
+
+    pub fn mark(&self, col: u16) -> Option<crate::command::Mark> {
+        self.marks
+            .binary_search_by_key(&col, |(col, _)| *col)
+            .ok()
+            .map(|i| self.marks[i].1)
+    }
+
+    pub fn set_mark(&mut self, col: u16, mark: crate::command::Mark) {
+        match self.marks.binary_search_by_key(&col, |(col, _)| *col) {
+            Ok(i) => self.marks[i].1 = mark,
+            Err(i) => self.marks.insert(i, (col, mark)),
+        }
     }
 
     pub fn wrap(&mut self, wrap: bool) {
@@ -83,6 +123,13 @@ impl Row {
         self.wrapped
     }
 
+    pub fn is_blank(&self) -> bool {
+        let default_cell = crate::Cell::new();
+        !self.wrapped
+            && self.marks.is_empty()
+            && self.cells().all(|cell| cell == &default_cell)
+    }
+
     pub fn clear_wide(&mut self, col: u16) {
         let cell = &self.cells[usize::from(col)];
         let other = if cell.is_wide() {
@@ -143,6 +190,7 @@ impl Row {
         wrapping: bool,
         prev_pos: Option<crate::grid::Pos>,
         prev_attrs: Option<crate::attrs::Attrs>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> (crate::grid::Pos, crate::attrs::Attrs) {
         let mut prev_was_wide = false;
         let default_cell = crate::Cell::new();
@@ -163,7 +211,11 @@ impl Row {
         if wrapping && first_cell == &default_cell {
             let default_attrs = default_cell.attrs();
             if &prev_attrs != default_attrs {
-                default_attrs.write_escape_code_diff(contents, &prev_attrs);
+                default_attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *default_attrs;
             }
             contents.push(b' ');
@@ -212,7 +264,11 @@ impl Row {
                     }
                     prev_pos = new_pos;
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
                     crate::term::EraseChar::new(pos.col - prev_col)
@@ -238,9 +294,17 @@ impl Row {
                     }
 
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
+                    if let Some(mark) = self.mark(col) {
+                        crate::term::SemanticPrompt::new(mark)
+                            .write_buf(contents);
+                    }
 
                     prev_pos.col += if cell.is_wide() { 2 } else { 1 };
                     let cell_contents = cell.contents();
@@ -270,7 +334,11 @@ impl Row {
             }
             prev_pos = new_pos;
             if &prev_attrs != attrs {
-                attrs.write_escape_code_diff(contents, &prev_attrs);
+                attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *attrs;
             }
             crate::term::ClearRowForward::default().write_buf(contents);
@@ -293,6 +361,7 @@ impl Row {
         prev_wrapping: bool,
         mut prev_pos: crate::grid::Pos,
         mut prev_attrs: crate::attrs::Attrs,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> (crate::grid::Pos, crate::attrs::Attrs) {
         let mut prev_was_wide = false;
 
@@ -307,8 +376,11 @@ impl Row {
         {
             let first_cell_attrs = first_cell.attrs();
             if &prev_attrs != first_cell_attrs {
-                first_cell_attrs
-                    .write_escape_code_diff(contents, &prev_attrs);
+                first_cell_attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *first_cell_attrs;
             }
             let mut cell_contents = prev_first_cell.contents();
@@ -370,7 +442,11 @@ impl Row {
                     }
                     prev_pos = new_pos;
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
                     crate::term::EraseChar::new(pos.col - prev_col)
@@ -379,7 +455,8 @@ impl Row {
                 }
             }
 
-            if cell != prev_cell {
+            let mark = self.mark(col);
+            if cell != prev_cell || mark != prev.mark(col) {
                 let attrs = cell.attrs();
                 if cell.has_contents() {
                     if pos != prev_pos {
@@ -396,9 +473,17 @@ impl Row {
                     }
 
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
+                    if let Some(mark) = mark {
+                        crate::term::SemanticPrompt::new(mark)
+                            .write_buf(contents);
+                    }
 
                     prev_pos.col += if cell.is_wide() { 2 } else { 1 };
                     contents.extend(cell.contents().as_bytes());
@@ -427,7 +512,11 @@ impl Row {
             }
             prev_pos = new_pos;
             if &prev_attrs != attrs {
-                attrs.write_escape_code_diff(contents, &prev_attrs);
+                attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *attrs;
             }
             crate::term::ClearRowForward::default().write_buf(contents);
@@ -463,7 +552,11 @@ impl Row {
             if end_cell.has_contents() {
                 let attrs = end_cell.attrs();
                 if &prev_attrs != attrs {
-                    attrs.write_escape_code_diff(contents, &prev_attrs);
+                    attrs.write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     prev_attrs = *attrs;
                 }
                 contents.extend(end_cell.contents().as_bytes());
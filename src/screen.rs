@@ -1,11 +1,35 @@
 use crate::term::BufWrite as _;
 use unicode_width::UnicodeWidthChar as _;
 
-const MODE_APPLICATION_KEYPAD: u8 = 0b0000_0001;
-const MODE_APPLICATION_CURSOR: u8 = 0b0000_0010;
-const MODE_HIDE_CURSOR: u8 = 0b0000_0100;
-const MODE_ALTERNATE_SCREEN: u8 = 0b0000_1000;
-const MODE_BRACKETED_PASTE: u8 = 0b0001_0000;
+const MODE_APPLICATION_KEYPAD: u16 = 0b0000_0000_0000_0001;
+const MODE_APPLICATION_CURSOR: u16 = 0b0000_0000_0000_0010;
+const MODE_HIDE_CURSOR: u16 = 0b0000_0000_0000_0100;
+const MODE_ALTERNATE_SCREEN: u16 = 0b0000_0000_0000_1000;
+const MODE_BRACKETED_PASTE: u16 = 0b0000_0000_0001_0000;
+const MODE_NO_AUTOWRAP: u16 = 0b0000_0000_0010_0000;
+const MODE_INSERT: u16 = 0b0000_0000_0100_0000;
+const MODE_NEWLINE: u16 = 0b0000_0000_1000_0000;
+const MODE_FOCUS_REPORTING: u16 = 0b0000_0001_0000_0000;
+const MODE_SYNCHRONIZED_OUTPUT: u16 = 0b0000_0010_0000_0000;
+const MODE_ALTERNATE_SCROLL: u16 = 0b0000_0100_0000_0000;
+
+// the hyperlink table is pruned of links which are no longer used by any
+// cells once it grows past this size (or twice the number of links which
+// were still in use the last time it was pruned, if that is larger)
+const MIN_HYPERLINK_PRUNE_LEN: usize = 1024;
+
+// the number of entries which can be saved via XTWINOPS 22, matching xterm
+const MAX_TITLE_STACK_LEN: usize = 10;
+
+// the number of entries in each kitty keyboard flags stack, matching kitty
+const MAX_KITTY_KEYBOARD_STACK_LEN: usize = 8;
+
+// the flags defined by the kitty keyboard protocol
+const KITTY_KEYBOARD_FLAGS_MASK: u8 = 0b1_1111;
+
+// the longest DCS request which will be answered, since none of the
+// supported requests need to be anywhere near this long
+const MAX_DCS_LEN: usize = 4096;
 
 /// The xterm mouse handling mode currently in use.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -21,7 +45,13 @@ pub enum MouseProtocolMode {
     /// Also known as VT200 mouse mode.
     PressRelease,
 
-    // Highlight,
+    /// Mouse button events should be reported on button press and release,
+    /// and the application may take over highlighting the selection. This
+    /// requires the application to answer each button press, which isn't
+    /// supported, so `Screen::mouse_event` doesn't report any events in
+    /// this mode.
+    Highlight,
+
     /// Mouse button events should be reported on button press and release, as
     /// well as when the mouse moves between cells while a button is held
     /// down.
@@ -31,7 +61,10 @@ pub enum MouseProtocolMode {
     /// and mouse motion events should be reported when the mouse moves
     /// between cells regardless of whether a button is held down or not.
     AnyMotion,
-    // DecLocator,
+
+    /// The DEC locator mode (enabled via DECELR), where the mouse position
+    /// is only reported in response to requests from the application.
+    DecLocator,
 }
 
 impl Default for MouseProtocolMode {
@@ -51,7 +84,12 @@ pub enum MouseProtocolEncoding {
 
     /// SGR-like encoding.
     Sgr,
-    // Urxvt,
+
+    /// Decimal encoding used by urxvt.
+    Urxvt,
+
+    /// SGR-like encoding, with positions given in pixels rather than cells.
+    SgrPixels,
 }
 
 impl Default for MouseProtocolEncoding {
@@ -60,6 +98,42 @@ impl Default for MouseProtocolEncoding {
     }
 }
 
+// the values reported by DECRPM in response to a mode query
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ModeState {
+    NotRecognized,
+    Set,
+    Reset,
+    PermanentlyReset,
+}
+
+impl From<bool> for ModeState {
+    fn from(set: bool) -> Self {
+        if set {
+            Self::Set
+        } else {
+            Self::Reset
+        }
+    }
+}
+
+// the DCS requests which are answered
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DcsRequest {
+    // DCS $ q
+    Decrqss,
+    // DCS + q
+    Xtgettcap,
+}
+
+// an entry saved by XTWINOPS 22, which may contain the icon name, the
+// window title, or both
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TitleStackEntry {
+    icon_name: Option<String>,
+    title: Option<String>,
+}
+
 /// Represents the overall terminal state.
 #[derive(Clone, Debug)]
 pub struct Screen {
@@ -69,12 +143,46 @@ pub struct Screen {
     attrs: crate::attrs::Attrs,
     saved_attrs: crate::attrs::Attrs,
 
+    charsets: crate::charset::Charsets,
+    saved_charsets: crate::charset::Charsets,
+
+    hyperlinks: crate::hyperlink::Hyperlinks,
+    hyperlink_prune_len: usize,
+
+    palette: crate::palette::Palette,
+
     title: String,
     icon_name: String,
+    title_stack: Vec<TitleStackEntry>,
+
+    working_directory_host: Option<String>,
+    working_directory: Option<String>,
 
-    modes: u8,
+    // the semantic prompt state from the most recent OSC 133, and whether
+    // it still needs to be recorded on the next cell drawn
+    semantic_prompt: crate::command::Mark,
+    semantic_prompt_pending: bool,
+
+    modes: u16,
     mouse_protocol_mode: MouseProtocolMode,
     mouse_protocol_encoding: MouseProtocolEncoding,
+
+    // set via DECSCUSR, and only used to answer DECRQSS
+    cursor_style: u16,
+
+    // the main and alternate screens each have their own stack of kitty
+    // keyboard flags, the last of which is active
+    kitty_keyboard_stack: Vec<u8>,
+    alternate_kitty_keyboard_stack: Vec<u8>,
+
+    // when the synchronized update in progress (if any) was started
+    synchronized_output_start: Option<std::time::Instant>,
+
+    // the DCS request being received, and its data so far
+    dcs: Option<(DcsRequest, Vec<u8>)>,
+
+    // replies to queries which haven't been delivered yet
+    replies: Vec<u8>,
 }
 
 impl Screen {
@@ -91,20 +199,46 @@ impl Screen {
             attrs: crate::attrs::Attrs::default(),
             saved_attrs: crate::attrs::Attrs::default(),
 
+            charsets: crate::charset::Charsets::default(),
+            saved_charsets: crate::charset::Charsets::default(),
+
+            hyperlinks: crate::hyperlink::Hyperlinks::default(),
+            hyperlink_prune_len: MIN_HYPERLINK_PRUNE_LEN,
+
+            palette: crate::palette::Palette::default(),
+
             title: String::default(),
             icon_name: String::default(),
+            title_stack: vec![],
+
+            working_directory_host: None,
+            working_directory: None,
+
+            semantic_prompt: crate::command::Mark::default(),
+            semantic_prompt_pending: false,
 
             modes: 0,
             mouse_protocol_mode: MouseProtocolMode::default(),
             mouse_protocol_encoding: MouseProtocolEncoding::default(),
+
+            cursor_style: 0,
+
+            kitty_keyboard_stack: vec![],
+            alternate_kitty_keyboard_stack: vec![],
+
+            synchronized_output_start: None,
+
+            dcs: None,
+
+            replies: vec![],
         }
     }
 
     /// Resizes the terminal.
     pub fn set_size(&mut self, rows: u16, cols: u16) {
-        self.grid.set_size(crate::grid::Size { rows, cols });
+        self.grid.set_size(crate::grid::Size { rows, cols }, true);
         self.alternate_grid
-            .set_size(crate::grid::Size { rows, cols });
+            .set_size(crate::grid::Size { rows, cols }, false);
     }
 
     /// Returns the current size of the terminal.
@@ -232,27 +366,200 @@ impl Screen {
         }
     }
 
+    /// Returns the semantic zone (prompt, input, or output) that the cell
+    /// at the given location was drawn in, as reported by OSC 133. Cells
+    /// without any contents are never part of a zone.
+    #[must_use]
+    pub fn semantic_zone(&self, row: u16, col: u16) -> crate::SemanticZone {
+        if !self.cell(row, col).is_some_and(crate::Cell::has_contents) {
+            return crate::SemanticZone::None;
+        }
+        let row = self.grid().scrollback_rows() - self.grid().scrollback()
+            + usize::from(row);
+        self.grid()
+            .mark_before((row, col))
+            .map_or(crate::SemanticZone::None, |mark| mark.zone)
+    }
+
+    /// Returns the commands found in the terminal contents (including the
+    /// scrollback) via the semantic prompt marks (OSC 133) emitted by the
+    /// shell, from oldest to newest.
+    #[must_use]
+    pub fn commands(&self) -> Vec<crate::Command> {
+        let grid = self.grid();
+        let mut commands = vec![];
+        // a new command's end is initialized to its start, and its actual
+        // end is found once we know where the next thing starts
+        let mut finish = |mut command: crate::Command, next| {
+            command.end = grid.contents_end(command.end, next);
+            commands.push(command);
+        };
+        let mut current: Option<crate::Command> = None;
+        let mut prev_zone = crate::attrs::SemanticZone::None;
+        for (pos, mark) in grid.marks() {
+            let new_command = match mark.zone {
+                crate::attrs::SemanticZone::None => {
+                    // anything drawn after the command ends isn't part of it
+                    if let Some(mut command) = current.take() {
+                        command.exit_status = mark.exit_status;
+                        finish(command, pos);
+                    }
+                    prev_zone = mark.zone;
+                    continue;
+                }
+                _ if mark.zone == prev_zone && !mark.prompt_start => continue,
+                crate::attrs::SemanticZone::Prompt => {
+                    mark.prompt_start || current.is_none()
+                }
+                // input can be split up by continuation prompts
+                crate::attrs::SemanticZone::Input
+                | crate::attrs::SemanticZone::Output => !matches!(
+                    &current,
+                    Some(command) if command.output.is_none()
+                ),
+            };
+            if new_command {
+                if let Some(mut command) = current.take() {
+                    if mark.prompt_start {
+                        command.exit_status = mark.exit_status;
+                    }
+                    finish(command, pos);
+                }
+            }
+            let command =
+                current.get_or_insert_with(|| crate::Command::new(pos));
+            match mark.zone {
+                crate::attrs::SemanticZone::None => {}
+                crate::attrs::SemanticZone::Prompt => {
+                    command.prompt.get_or_insert(pos);
+                }
+                crate::attrs::SemanticZone::Input => {
+                    command.input.get_or_insert(pos);
+                }
+                crate::attrs::SemanticZone::Output => {
+                    command.output.get_or_insert(pos);
+                }
+            }
+            prev_zone = mark.zone;
+        }
+        if let Some(mut command) = current {
+            // the last command may have ended without a new prompt being
+            // drawn yet
+            if self.semantic_prompt_pending
+                && (self.semantic_prompt.zone
+                    == crate::attrs::SemanticZone::None
+                    || self.semantic_prompt.prompt_start)
+            {
+                command.exit_status = self.semantic_prompt.exit_status;
+            }
+            finish(command, (usize::MAX, 0));
+        }
+        commands
+    }
+
+    /// Returns the text typed at the prompt for the given command, or
+    /// `None` if the shell didn't report where it starts.
+    #[must_use]
+    pub fn command_input(&self, command: &crate::Command) -> Option<String> {
+        let start = command.input_start()?;
+        let end = command.output_start().unwrap_or_else(|| command.end());
+        let mut contents = String::new();
+        self.grid()
+            .write_contents_between(&mut contents, start, end);
+        Some(contents.trim_end().to_string())
+    }
+
+    /// Returns the output of the given command, or `None` if it hasn't
+    /// started running.
+    #[must_use]
+    pub fn command_output(&self, command: &crate::Command) -> Option<String> {
+        let start = command.output_start()?;
+        let mut contents = String::new();
+        self.grid().write_contents_between(
+            &mut contents,
+            start,
+            command.end(),
+        );
+        Some(contents)
+    }
+
+    /// Returns the output of the most recent command which produced any
+    /// output.
+    #[must_use]
+    pub fn last_command_output(&self) -> Option<String> {
+        self.commands()
+            .iter()
+            .rev()
+            .find_map(|command| self.command_output(command))
+    }
+
+    /// Scrolls back to put the closest prompt above the top of the screen
+    /// at the top of the screen. Returns `false` if there is no such prompt.
+    pub fn scroll_to_previous_prompt(&mut self) -> bool {
+        let top = self.grid().scrollback_rows() - self.scrollback();
+        let Some(row) =
+            self.prompt_rows().take_while(|row| *row < top).last()
+        else {
+            return false;
+        };
+        self.set_scrollback(self.grid().scrollback_rows() - row);
+        true
+    }
+
+    /// Scrolls forward to put the closest prompt below the top of the
+    /// screen at the top of the screen (or as close to it as possible, if
+    /// the prompt is on the normal screen). Returns `false` if there is no
+    /// such prompt, or if the screen isn't scrolled back at all.
+    pub fn scroll_to_next_prompt(&mut self) -> bool {
+        if self.scrollback() == 0 {
+            return false;
+        }
+        let top = self.grid().scrollback_rows() - self.scrollback();
+        let Some(row) = self.prompt_rows().find(|row| *row > top) else {
+            return false;
+        };
+        self.set_scrollback(
+            self.grid().scrollback_rows().saturating_sub(row),
+        );
+        true
+    }
+
+    // the rows in all_rows where a new prompt starts
+    fn prompt_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.grid()
+            .marks()
+            .filter(|(_, mark)| {
+                mark.zone == crate::attrs::SemanticZone::Prompt
+                    && mark.prompt_start
+            })
+            .map(|((row, _), _)| row)
+    }
+
     /// Return escape codes sufficient to reproduce the entire contents of the
     /// current terminal state. This is a convenience wrapper around
-    /// `contents_formatted`, `input_mode_formatted`, and `title_formatted`.
+    /// `contents_formatted`, `input_mode_formatted`, `title_formatted`, and
+    /// `palette_formatted`.
     #[must_use]
     pub fn state_formatted(&self) -> Vec<u8> {
         let mut contents = vec![];
         self.write_contents_formatted(&mut contents);
         self.write_input_mode_formatted(&mut contents);
         self.write_title_formatted(&mut contents);
+        self.write_palette_formatted(&mut contents);
         contents
     }
 
     /// Return escape codes sufficient to turn the terminal state of the
     /// screen `prev` into the current terminal state. This is a convenience
-    /// wrapper around `contents_diff`, `input_mode_diff`, and `title_diff`
+    /// wrapper around `contents_diff`, `input_mode_diff`, `title_diff`, and
+    /// `palette_diff`.
     #[must_use]
     pub fn state_diff(&self, prev: &Self) -> Vec<u8> {
         let mut contents = vec![];
         self.write_contents_diff(&mut contents, prev);
         self.write_input_mode_diff(&mut contents, prev);
         self.write_title_diff(&mut contents, prev);
+        self.write_palette_diff(&mut contents, prev);
         contents
     }
 
@@ -289,8 +596,24 @@ impl Screen {
         where R: Iterator<Item = &'a crate::row::Row>
     {
         crate::term::HideCursor::new(self.hide_cursor()).write_buf(contents);
-        let prev_attrs = self.grid().write_contents_formatted_from_rows(rows, contents);
-        self.attrs.write_escape_code_diff(contents, &prev_attrs);
+        let prev_attrs = self.grid().write_contents_formatted_from_rows(
+            rows,
+            contents,
+            &self.hyperlinks,
+        );
+        self.attrs.write_escape_code_diff(
+            contents,
+            &prev_attrs,
+            &self.hyperlinks,
+        );
+        if self.semantic_prompt_pending {
+            crate::term::SemanticPrompt::new(self.semantic_prompt)
+                .write_buf(contents);
+        }
+        self.charsets.write_escape_code_diff(
+            contents,
+            &crate::charset::Charsets::default(),
+        );
     }
 
     /// Returns the formatted visible contents of the terminal by row,
@@ -316,7 +639,7 @@ impl Screen {
             // visible_rows can never return enough rows to overflow here
             let i = i.try_into().unwrap();
             let mut contents = vec![];
-            row.write_contents_formatted(
+            let (_, attrs) = row.write_contents_formatted(
                 &mut contents,
                 start,
                 width,
@@ -324,7 +647,12 @@ impl Screen {
                 wrapping,
                 None,
                 None,
+                &self.hyperlinks,
             );
+            // hyperlinks shouldn't leak into whatever the caller draws next
+            if attrs.hyperlink.is_some() {
+                crate::term::Hyperlink::new(None).write_buf(&mut contents);
+            }
             if start == 0 && width == self.grid.size().cols {
                 wrapping = row.wrapped();
             }
@@ -354,12 +682,58 @@ impl Screen {
             crate::term::HideCursor::new(self.hide_cursor())
                 .write_buf(contents);
         }
+        let mut grid_contents = vec![];
         let prev_attrs = self.grid().write_contents_diff(
-            contents,
+            &mut grid_contents,
             prev.grid(),
             prev.attrs,
+            &self.hyperlinks,
+        );
+        if grid_contents.is_empty() {
+            self.charsets
+                .write_escape_code_diff(contents, &prev.charsets);
+        } else {
+            // the grid contents are drawn using the default character sets,
+            // with autowrap enabled and insert and newline modes disabled,
+            // so they need to be switched back before drawing. the modes
+            // are restored to their previous state afterwards, since they
+            // are handled by input_mode_diff.
+            let default_charsets = crate::charset::Charsets::default();
+            default_charsets.write_escape_code_diff(contents, &prev.charsets);
+            if !prev.autowrap() {
+                crate::term::Autowrap::new(true).write_buf(contents);
+            }
+            if prev.insert_mode() {
+                crate::term::InsertMode::new(false).write_buf(contents);
+            }
+            if prev.newline_mode() {
+                crate::term::NewlineMode::new(false).write_buf(contents);
+            }
+            contents.extend_from_slice(&grid_contents);
+            if !prev.autowrap() {
+                crate::term::Autowrap::new(false).write_buf(contents);
+            }
+            if prev.insert_mode() {
+                crate::term::InsertMode::new(true).write_buf(contents);
+            }
+            if prev.newline_mode() {
+                crate::term::NewlineMode::new(true).write_buf(contents);
+            }
+            self.charsets
+                .write_escape_code_diff(contents, &default_charsets);
+        }
+        self.attrs.write_escape_code_diff(
+            contents,
+            &prev_attrs,
+            &self.hyperlinks,
         );
-        self.attrs.write_escape_code_diff(contents, &prev_attrs);
+        if self.semantic_prompt_pending
+            && (!prev.semantic_prompt_pending
+                || self.semantic_prompt != prev.semantic_prompt)
+        {
+            crate::term::SemanticPrompt::new(self.semantic_prompt)
+                .write_buf(contents);
+        }
     }
 
     /// Returns a sequence of terminal byte streams sufficient to turn the
@@ -387,7 +761,7 @@ impl Screen {
                 // visible_rows can never return enough rows to overflow here
                 let i = i.try_into().unwrap();
                 let mut contents = vec![];
-                row.write_contents_diff(
+                let (_, attrs) = row.write_contents_diff(
                     &mut contents,
                     prev_row,
                     start,
@@ -397,7 +771,12 @@ impl Screen {
                     false,
                     crate::grid::Pos { row: i, col: start },
                     crate::attrs::Attrs::default(),
+                    &self.hyperlinks,
                 );
+                if attrs.hyperlink.is_some() {
+                    crate::term::Hyperlink::new(None)
+                        .write_buf(&mut contents);
+                }
                 contents
             })
     }
@@ -410,6 +789,12 @@ impl Screen {
     /// * application cursor
     /// * bracketed paste
     /// * xterm mouse support
+    /// * autowrap
+    /// * insert mode
+    /// * newline mode
+    /// * focus reporting
+    /// * alternate scroll
+    /// * kitty keyboard protocol flags (for the current screen only)
     #[must_use]
     pub fn input_mode_formatted(&self) -> Vec<u8> {
         let mut contents = vec![];
@@ -438,6 +823,26 @@ impl Screen {
             MouseProtocolEncoding::Default,
         )
         .write_buf(contents);
+        if !self.autowrap() {
+            crate::term::Autowrap::new(false).write_buf(contents);
+        }
+        if self.insert_mode() {
+            crate::term::InsertMode::new(true).write_buf(contents);
+        }
+        if self.newline_mode() {
+            crate::term::NewlineMode::new(true).write_buf(contents);
+        }
+        if self.focus_reporting() {
+            crate::term::FocusReporting::new(true).write_buf(contents);
+        }
+        if self.alternate_scroll() {
+            crate::term::AlternateScroll::new(true).write_buf(contents);
+        }
+        crate::term::KittyKeyboardFlags::new(
+            self.kitty_keyboard_stack(),
+            &[],
+        )
+        .write_buf(contents);
     }
 
     /// Returns terminal escape sequences sufficient to change the previous
@@ -482,6 +887,30 @@ impl Screen {
             prev.mouse_protocol_encoding,
         )
         .write_buf(contents);
+        if self.autowrap() != prev.autowrap() {
+            crate::term::Autowrap::new(self.autowrap()).write_buf(contents);
+        }
+        if self.insert_mode() != prev.insert_mode() {
+            crate::term::InsertMode::new(self.insert_mode())
+                .write_buf(contents);
+        }
+        if self.newline_mode() != prev.newline_mode() {
+            crate::term::NewlineMode::new(self.newline_mode())
+                .write_buf(contents);
+        }
+        if self.focus_reporting() != prev.focus_reporting() {
+            crate::term::FocusReporting::new(self.focus_reporting())
+                .write_buf(contents);
+        }
+        if self.alternate_scroll() != prev.alternate_scroll() {
+            crate::term::AlternateScroll::new(self.alternate_scroll())
+                .write_buf(contents);
+        }
+        crate::term::KittyKeyboardFlags::new(
+            self.kitty_keyboard_stack(),
+            prev.kitty_keyboard_stack(),
+        )
+        .write_buf(contents);
     }
 
     /// Returns terminal escape sequences sufficient to set the current
@@ -494,8 +923,7 @@ impl Screen {
     }
 
     fn write_title_formatted(&self, contents: &mut Vec<u8>) {
-        crate::term::ChangeTitle::new(&self.icon_name, &self.title, "", "")
-            .write_buf(contents);
+        self.write_title_changes(contents, &[], "", "");
     }
 
     /// Returns terminal escape sequences sufficient to change the previous
@@ -509,15 +937,98 @@ impl Screen {
     }
 
     fn write_title_diff(&self, contents: &mut Vec<u8>, prev: &Self) {
+        self.write_title_changes(
+            contents,
+            &prev.title_stack,
+            &prev.icon_name,
+            &prev.title,
+        );
+    }
+
+    fn write_title_changes(
+        &self,
+        contents: &mut Vec<u8>,
+        prev_stack: &[TitleStackEntry],
+        prev_icon_name: &str,
+        prev_title: &str,
+    ) {
+        let common = self
+            .title_stack
+            .iter()
+            .zip(prev_stack)
+            .take_while(|(entry, prev_entry)| entry == prev_entry)
+            .count();
+
+        let mut icon_name = prev_icon_name;
+        let mut title = prev_title;
+        for entry in prev_stack[common..].iter().rev() {
+            crate::term::PopTitle::new(0).write_buf(contents);
+            if let Some(entry_icon_name) = &entry.icon_name {
+                icon_name = entry_icon_name;
+            }
+            if let Some(entry_title) = &entry.title {
+                title = entry_title;
+            }
+        }
+        for entry in &self.title_stack[common..] {
+            let entry_icon_name = entry.icon_name.as_deref();
+            let entry_title = entry.title.as_deref();
+            crate::term::ChangeTitle::new(
+                entry_icon_name.unwrap_or(icon_name),
+                entry_title.unwrap_or(title),
+                icon_name,
+                title,
+            )
+            .write_buf(contents);
+            icon_name = entry_icon_name.unwrap_or(icon_name);
+            title = entry_title.unwrap_or(title);
+            let selector = match (entry_icon_name, entry_title) {
+                (Some(_), None) => 1,
+                (None, Some(_)) => 2,
+                _ => 0,
+            };
+            crate::term::PushTitle::new(selector).write_buf(contents);
+        }
+
         crate::term::ChangeTitle::new(
             &self.icon_name,
             &self.title,
-            &prev.icon_name,
-            &prev.title,
+            icon_name,
+            title,
         )
         .write_buf(contents);
     }
 
+    /// Returns terminal escape sequences sufficient to set the colors which
+    /// were overridden in the current terminal's palette (including the
+    /// default foreground, background, and cursor colors).
+    #[must_use]
+    pub fn palette_formatted(&self) -> Vec<u8> {
+        let mut contents = vec![];
+        self.write_palette_formatted(&mut contents);
+        contents
+    }
+
+    fn write_palette_formatted(&self, contents: &mut Vec<u8>) {
+        self.palette.write_escape_code_diff(
+            contents,
+            &crate::palette::Palette::default(),
+        );
+    }
+
+    /// Returns terminal escape sequences sufficient to change the previous
+    /// terminal's palette to the palette of the current terminal.
+    #[must_use]
+    pub fn palette_diff(&self, prev: &Self) -> Vec<u8> {
+        let mut contents = vec![];
+        self.write_palette_diff(&mut contents, prev);
+        contents
+    }
+
+    fn write_palette_diff(&self, contents: &mut Vec<u8>, prev: &Self) {
+        self.palette.write_escape_code_diff(contents, &prev.palette);
+    }
+
     /// Returns terminal escape sequences sufficient to set the current
     /// terminal's drawing attributes.
     ///
@@ -528,6 +1039,13 @@ impl Screen {
     /// * italic
     /// * underline
     /// * inverse
+    /// * dim
+    /// * blink
+    /// * hidden
+    /// * strikethrough
+    /// * double underline
+    /// * overline
+    /// * designated and shifted character sets
     ///
     /// This is not typically necessary, since `contents_formatted` will leave
     /// the current active drawing attributes in the correct state, but this
@@ -546,6 +1064,15 @@ impl Screen {
         self.attrs.write_escape_code_diff(
             contents,
             &crate::attrs::Attrs::default(),
+            &self.hyperlinks,
+        );
+        if self.semantic_prompt_pending {
+            crate::term::SemanticPrompt::new(self.semantic_prompt)
+                .write_buf(contents);
+        }
+        self.charsets.write_escape_code_diff(
+            contents,
+            &crate::charset::Charsets::default(),
         );
     }
 
@@ -582,8 +1109,12 @@ impl Screen {
 
     fn write_cursor_state_formatted(&self, contents: &mut Vec<u8>) {
         crate::term::HideCursor::new(self.hide_cursor()).write_buf(contents);
-        self.grid()
-            .write_cursor_position_formatted(contents, None, None);
+        self.grid().write_cursor_position_formatted(
+            contents,
+            None,
+            None,
+            &self.hyperlinks,
+        );
 
         // we don't just call write_attributes_formatted here, because that
         // would still be confusing - consider the case where the user sets
@@ -600,6 +1131,15 @@ impl Screen {
         self.grid().visible_cell(crate::grid::Pos { row, col })
     }
 
+    /// Returns the hyperlink attached to the given cell via OSC 8, if any.
+    /// The cell should come from this screen.
+    #[must_use]
+    pub fn hyperlink(&self, cell: &crate::Cell) -> Option<&crate::Hyperlink> {
+        cell.attrs()
+            .hyperlink
+            .and_then(|id| self.hyperlinks.get(id))
+    }
+
     /// Returns whether the text in row `row` should wrap to the next line.
     #[must_use]
     pub fn row_wrapped(&self, row: u16) -> bool {
@@ -620,6 +1160,49 @@ impl Screen {
         &self.icon_name
     }
 
+    /// Returns the working directory most recently reported by the
+    /// application via OSC 7, if any.
+    #[must_use]
+    pub fn working_directory(&self) -> Option<&str> {
+        self.working_directory.as_deref()
+    }
+
+    /// Returns the host name that was reported along with the working
+    /// directory via OSC 7, if any.
+    #[must_use]
+    pub fn working_directory_host(&self) -> Option<&str> {
+        self.working_directory_host.as_deref()
+    }
+
+    /// Returns the color that the given palette index was set to with OSC 4,
+    /// or `None` if it hasn't been overridden. The return value will be
+    /// (red, green, blue).
+    #[must_use]
+    pub fn palette_color(&self, idx: u8) -> Option<(u8, u8, u8)> {
+        self.palette.colors.get(&idx).copied()
+    }
+
+    /// Returns the default foreground color set with OSC 10, or `None` if it
+    /// hasn't been overridden. The return value will be (red, green, blue).
+    #[must_use]
+    pub fn foreground_color(&self) -> Option<(u8, u8, u8)> {
+        self.palette.foreground
+    }
+
+    /// Returns the default background color set with OSC 11, or `None` if it
+    /// hasn't been overridden. The return value will be (red, green, blue).
+    #[must_use]
+    pub fn background_color(&self) -> Option<(u8, u8, u8)> {
+        self.palette.background
+    }
+
+    /// Returns the cursor color set with OSC 12, or `None` if it hasn't been
+    /// overridden. The return value will be (red, green, blue).
+    #[must_use]
+    pub fn cursor_color(&self) -> Option<(u8, u8, u8)> {
+        self.palette.cursor
+    }
+
     /// Returns whether the alternate screen is currently in use.
     #[must_use]
     pub fn alternate_screen(&self) -> bool {
@@ -650,6 +1233,147 @@ impl Screen {
         self.mode(MODE_BRACKETED_PASTE)
     }
 
+    /// Returns whether the terminal should wrap text at the right margin.
+    #[must_use]
+    pub fn autowrap(&self) -> bool {
+        !self.mode(MODE_NO_AUTOWRAP)
+    }
+
+    /// Returns whether the terminal should be in insert mode.
+    #[must_use]
+    pub fn insert_mode(&self) -> bool {
+        self.mode(MODE_INSERT)
+    }
+
+    /// Returns whether the terminal should be in newline mode, where
+    /// linefeeds also perform a carriage return.
+    #[must_use]
+    pub fn newline_mode(&self) -> bool {
+        self.mode(MODE_NEWLINE)
+    }
+
+    /// Returns whether the application has requested to be notified when
+    /// the terminal gains or loses focus.
+    #[must_use]
+    pub fn focus_reporting(&self) -> bool {
+        self.mode(MODE_FOCUS_REPORTING)
+    }
+
+    /// Returns the bytes to send to the application when the terminal gains
+    /// (`CSI I`) or loses (`CSI O`) focus. This is empty if the application
+    /// has not enabled focus reporting.
+    #[must_use]
+    pub fn focus_event(&self, focused: bool) -> Vec<u8> {
+        let mut contents = vec![];
+        if self.focus_reporting() {
+            crate::term::FocusEvent::new(focused).write_buf(&mut contents);
+        }
+        contents
+    }
+
+    /// Returns whether the application is in the middle of a synchronized
+    /// update (between `\e[?2026h` and `\e[?2026l`), in which case the
+    /// screen may only contain a partially drawn frame.
+    #[must_use]
+    pub fn synchronized_output(&self) -> bool {
+        self.mode(MODE_SYNCHRONIZED_OUTPUT)
+    }
+
+    /// Returns the kitty keyboard protocol flags requested by the
+    /// application for the current screen (main or alternate), as a
+    /// bitmask: 1 to disambiguate escape codes, 2 to report event types, 4
+    /// to report alternate keys, 8 to report all keys as escape codes, and
+    /// 16 to report associated text. This is 0 if the application hasn't
+    /// enabled the protocol.
+    ///
+    /// Only the current screen's flags are included in
+    /// `input_mode_formatted` and `input_mode_diff`, since the formatted
+    /// output doesn't switch screens. The flags for the other screen are
+    /// lost when the output is replayed, so switching screens afterwards
+    /// may report different flags than the original.
+    #[must_use]
+    pub fn kitty_keyboard_flags(&self) -> u8 {
+        self.kitty_keyboard_stack().last().copied().unwrap_or(0)
+    }
+
+    /// Returns the bytes to send to the application for the given key
+    /// event, based on the input modes it has enabled (application cursor
+    /// and keypad modes, newline mode, and the kitty keyboard protocol).
+    /// This is empty for events which the application has not asked to be
+    /// told about, such as key releases.
+    #[must_use]
+    pub fn key_event(&self, event: &crate::KeyEvent) -> Vec<u8> {
+        let mut contents = vec![];
+        event.write_buf(
+            &mut contents,
+            &crate::key::InputModes {
+                application_cursor: self.application_cursor(),
+                application_keypad: self.application_keypad(),
+                newline: self.newline_mode(),
+                kitty_flags: self.kitty_keyboard_flags(),
+            },
+        );
+        contents
+    }
+
+    /// Returns the bytes to send to the application for the given pasted
+    /// text. If bracketed paste mode is enabled, the text is wrapped in the
+    /// bracketed paste markers, with any end markers inside the text
+    /// removed. Otherwise, control characters are removed if requested.
+    /// In either case, newlines are sent as carriage returns.
+    #[must_use]
+    pub fn paste(&self, paste: &crate::Paste<'_>) -> Vec<u8> {
+        let mut contents = vec![];
+        paste.write_buf(&mut contents, self.bracketed_paste());
+        contents
+    }
+
+    /// Returns whether mouse wheel events should be sent to the application
+    /// as cursor keys while the alternate screen is active and mouse
+    /// reporting is disabled.
+    #[must_use]
+    pub fn alternate_scroll(&self) -> bool {
+        self.mode(MODE_ALTERNATE_SCROLL)
+    }
+
+    /// Returns the bytes to send to the application for the given mouse
+    /// event, based on the active `MouseProtocolMode` and
+    /// `MouseProtocolEncoding`. This is empty if the application hasn't
+    /// asked to be told about this kind of event, or if the position can't
+    /// be represented in the active encoding. Nothing is reported in
+    /// `MouseProtocolMode::Highlight`, since the handshake it requires
+    /// with the application isn't supported. If `alternate_scroll` is
+    /// enabled, vertical wheel events on the alternate screen are sent as
+    /// cursor keys instead when mouse reporting is disabled.
+    #[must_use]
+    pub fn mouse_event(&self, event: &crate::MouseEvent) -> Vec<u8> {
+        if self.mouse_protocol_mode == MouseProtocolMode::None
+            && self.alternate_scroll()
+            && self.alternate_screen()
+            && event.kind == crate::MouseEventKind::Press
+        {
+            let key = match event.button {
+                Some(crate::MouseButton::WheelUp) => Some(crate::Key::Up),
+                Some(crate::MouseButton::WheelDown) => Some(crate::Key::Down),
+                _ => None,
+            };
+            if let Some(key) = key {
+                return self.key_event(&crate::KeyEvent::new(
+                    key,
+                    crate::Modifiers::default(),
+                ));
+            }
+        }
+
+        let mut contents = vec![];
+        event.write_buf(
+            &mut contents,
+            self.mouse_protocol_mode,
+            self.mouse_protocol_encoding,
+        );
+        contents
+    }
+
     /// Returns the currently active `MouseProtocolMode`
     #[must_use]
     pub fn mouse_protocol_mode(&self) -> MouseProtocolMode {
@@ -718,6 +1442,22 @@ impl Screen {
         }
     }
 
+    fn kitty_keyboard_stack(&self) -> &[u8] {
+        if self.mode(MODE_ALTERNATE_SCREEN) {
+            &self.alternate_kitty_keyboard_stack
+        } else {
+            &self.kitty_keyboard_stack
+        }
+    }
+
+    fn kitty_keyboard_stack_mut(&mut self) -> &mut Vec<u8> {
+        if self.mode(MODE_ALTERNATE_SCREEN) {
+            &mut self.alternate_kitty_keyboard_stack
+        } else {
+            &mut self.kitty_keyboard_stack
+        }
+    }
+
     fn enter_alternate_grid(&mut self) {
         self.grid_mut().set_scrollback(0);
         self.set_mode(MODE_ALTERNATE_SCREEN);
@@ -731,30 +1471,114 @@ impl Screen {
     fn save_cursor(&mut self) {
         self.grid_mut().save_cursor();
         self.saved_attrs = self.attrs;
+        self.saved_charsets = self.charsets;
     }
 
     fn restore_cursor(&mut self) {
         self.grid_mut().restore_cursor();
+        // the active hyperlink isn't part of the saved cursor state
+        let hyperlink = self.attrs.hyperlink;
         self.attrs = self.saved_attrs;
+        self.attrs.hyperlink = hyperlink;
+        self.charsets = self.saved_charsets;
     }
 
-    fn set_mode(&mut self, mode: u8) {
-        self.modes |= mode;
+    fn intern_hyperlink(
+        &mut self,
+        hyperlink: crate::Hyperlink,
+    ) -> std::num::NonZeroU32 {
+        if let Some(id) = self.hyperlinks.id(&hyperlink) {
+            return id;
+        }
+        if self.hyperlinks.len() >= self.hyperlink_prune_len {
+            let used: std::collections::HashSet<_> = self
+                .grid
+                .all_rows()
+                .chain(self.alternate_grid.all_rows())
+                .flat_map(crate::row::Row::cells)
+                .map(crate::Cell::attrs)
+                .chain([&self.attrs, &self.saved_attrs])
+                .filter_map(|attrs| attrs.hyperlink)
+                .collect();
+            self.hyperlinks.retain(&used);
+            self.hyperlink_prune_len =
+                MIN_HYPERLINK_PRUNE_LEN.max(self.hyperlinks.len() * 2);
+        }
+        self.hyperlinks.insert(hyperlink)
     }
 
-    fn clear_mode(&mut self, mode: u8) {
-        self.modes &= !mode;
+    pub(crate) fn synchronized_output_start(
+        &self,
+    ) -> Option<std::time::Instant> {
+        self.synchronized_output_start
     }
 
-    fn mode(&self, mode: u8) -> bool {
-        self.modes & mode != 0
+    pub(crate) fn end_synchronized_output(&mut self) {
+        self.clear_mode(MODE_SYNCHRONIZED_OUTPUT);
+        self.synchronized_output_start = None;
     }
 
-    fn set_mouse_mode(&mut self, mode: MouseProtocolMode) {
-        self.mouse_protocol_mode = mode;
+    pub(crate) fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.replies)
     }
 
-    fn clear_mouse_mode(&mut self, mode: MouseProtocolMode) {
+    fn set_mode(&mut self, mode: u16) {
+        self.modes |= mode;
+    }
+
+    fn clear_mode(&mut self, mode: u16) {
+        self.modes &= !mode;
+    }
+
+    // the registry of modes which can be set via SM and RM, for DECRQM
+    fn ansi_mode_state(&self, mode: u16) -> ModeState {
+        match mode {
+            4 => self.mode(MODE_INSERT).into(),
+            20 => self.mode(MODE_NEWLINE).into(),
+            _ => ModeState::NotRecognized,
+        }
+    }
+
+    // the registry of modes which can be set via DECSET and DECRST, for
+    // DECRQM
+    fn dec_mode_state(&self, mode: u16) -> ModeState {
+        let mouse_mode = |mouse_mode| self.mouse_protocol_mode == mouse_mode;
+        let mouse_encoding =
+            |encoding| self.mouse_protocol_encoding == encoding;
+        match mode {
+            1 => self.mode(MODE_APPLICATION_CURSOR).into(),
+            6 => self.grid().origin_mode().into(),
+            7 => (!self.mode(MODE_NO_AUTOWRAP)).into(),
+            9 => mouse_mode(MouseProtocolMode::Press).into(),
+            25 => (!self.mode(MODE_HIDE_CURSOR)).into(),
+            47 | 1049 => self.mode(MODE_ALTERNATE_SCREEN).into(),
+            1000 => mouse_mode(MouseProtocolMode::PressRelease).into(),
+            1001 => mouse_mode(MouseProtocolMode::Highlight).into(),
+            1002 => mouse_mode(MouseProtocolMode::ButtonMotion).into(),
+            1003 => mouse_mode(MouseProtocolMode::AnyMotion).into(),
+            1004 => self.mode(MODE_FOCUS_REPORTING).into(),
+            1005 => mouse_encoding(MouseProtocolEncoding::Utf8).into(),
+            1006 => mouse_encoding(MouseProtocolEncoding::Sgr).into(),
+            1007 => self.alternate_scroll().into(),
+            1015 => mouse_encoding(MouseProtocolEncoding::Urxvt).into(),
+            1016 => mouse_encoding(MouseProtocolEncoding::SgrPixels).into(),
+            2004 => self.mode(MODE_BRACKETED_PASTE).into(),
+            2026 => self.synchronized_output().into(),
+            // character widths are always computed per codepoint
+            2027 => ModeState::PermanentlyReset,
+            _ => ModeState::NotRecognized,
+        }
+    }
+
+    fn mode(&self, mode: u16) -> bool {
+        self.modes & mode != 0
+    }
+
+    fn set_mouse_mode(&mut self, mode: MouseProtocolMode) {
+        self.mouse_protocol_mode = mode;
+    }
+
+    fn clear_mouse_mode(&mut self, mode: MouseProtocolMode) {
         if self.mouse_protocol_mode == mode {
             self.mouse_protocol_mode = MouseProtocolMode::default();
         }
@@ -773,6 +1597,7 @@ impl Screen {
 
 impl Screen {
     pub(crate) fn text(&mut self, c: char) {
+        let c = self.charsets.translate(c);
         let pos = self.grid().pos();
         let size = self.grid().size();
         let attrs = self.attrs;
@@ -812,9 +1637,42 @@ impl Screen {
                 wrap = true;
             }
         }
-        self.grid_mut().col_wrap(width, wrap);
+        if self.autowrap() {
+            self.grid_mut().col_wrap(width, wrap);
+        } else if pos.col > size.cols - width {
+            // without autowrap, text at the right margin overwrites the end
+            // of the current row instead
+            self.grid_mut().col_set(size.cols - width);
+        }
         let pos = self.grid().pos();
 
+        if width > 0 && self.insert_mode() {
+            let row = self
+                .grid_mut()
+                .drawing_row_mut(pos.row)
+                // we assume self.grid().pos().row is always valid
+                .unwrap();
+            // inserting in the middle of a wide character splits it, so
+            // clear out both halves first
+            if row
+                .get(pos.col)
+                // pos.col is valid because we called col_wrap() immediately
+                // before this, which ensures that self.grid().pos().col has
+                // a valid value.
+                .unwrap()
+                .is_wide_continuation()
+            {
+                row.clear_wide(pos.col);
+                // pos.col was just checked above
+                let cell = row.get_mut(pos.col).unwrap();
+                cell.clear(*cell.attrs());
+            }
+            for _ in 0..width {
+                row.insert(pos.col, crate::Cell::new());
+            }
+            row.truncate(size.cols);
+        }
+
         if width == 0 {
             if pos.col > 0 {
                 let mut prev_cell = self
@@ -950,6 +1808,15 @@ impl Screen {
                 // that self.grid().pos().col has a valid value.
                 .unwrap();
             cell.set(c, attrs);
+            if self.semantic_prompt_pending {
+                let mark = self.semantic_prompt;
+                self.grid_mut().current_row_mut().set_mark(pos.col, mark);
+                self.semantic_prompt_pending = false;
+                // only the first cell drawn after OSC 133 A starts the
+                // prompt
+                self.semantic_prompt.prompt_start = false;
+                self.semantic_prompt.exit_status = None;
+            }
             self.grid_mut().col_inc(1);
             if width > 1 {
                 let pos = self.grid().pos();
@@ -1008,6 +1875,9 @@ impl Screen {
                 next_cell.set_wide_continuation(true);
                 self.grid_mut().col_inc(1);
             }
+            if !self.autowrap() {
+                self.grid_mut().col_clamp();
+            }
         }
     }
 
@@ -1018,11 +1888,14 @@ impl Screen {
     }
 
     pub(crate) fn tab(&mut self) {
-        self.grid_mut().col_tab();
+        self.grid_mut().col_tab(1);
     }
 
     pub(crate) fn lf(&mut self) {
         self.grid_mut().row_inc_scroll(1);
+        if self.newline_mode() {
+            self.cr();
+        }
     }
 
     pub(crate) fn vt(&mut self) {
@@ -1037,8 +1910,25 @@ impl Screen {
         self.grid_mut().col_set(0);
     }
 
+    pub(crate) fn so(&mut self) {
+        self.charsets.gl = 1;
+    }
+
+    pub(crate) fn si(&mut self) {
+        self.charsets.gl = 0;
+    }
+
     // escape codes
 
+    // ESC ( / ESC ) / ESC * / ESC +
+    pub(crate) fn scs(&mut self, slot: usize, b: u8) {
+        if let Some(charset) = crate::charset::Charset::from_designator(b) {
+            self.charsets.designate(slot, charset);
+        } else {
+            log::debug!("unhandled character set designation: {b}");
+        }
+    }
+
     // ESC 7
     pub(crate) fn decsc(&mut self) {
         self.save_cursor();
@@ -1059,20 +1949,47 @@ impl Screen {
         self.clear_mode(MODE_APPLICATION_KEYPAD);
     }
 
+    // ESC H
+    pub(crate) fn hts(&mut self) {
+        self.grid_mut().set_tab();
+    }
+
     // ESC M
     pub(crate) fn ri(&mut self) {
         self.grid_mut().row_dec_scroll(1);
     }
 
+    // ESC N
+    pub(crate) fn ss2(&mut self) {
+        self.charsets.single_shift = Some(2);
+    }
+
+    // ESC O
+    pub(crate) fn ss3(&mut self) {
+        self.charsets.single_shift = Some(3);
+    }
+
     // ESC c
     pub(crate) fn ris(&mut self) {
         let title = self.title.clone();
         let icon_name = self.icon_name.clone();
+        let title_stack = std::mem::take(&mut self.title_stack);
+        let working_directory_host = self.working_directory_host.take();
+        let working_directory = self.working_directory.take();
+        // ids are never reused, so that diffs against an earlier copy of the
+        // screen don't confuse one link with another
+        let hyperlinks = std::mem::take(&mut self.hyperlinks);
+        let hyperlink_prune_len = self.hyperlink_prune_len;
 
         *self = Self::new(self.grid.size(), self.grid.scrollback_len());
 
         self.title = title;
         self.icon_name = icon_name;
+        self.title_stack = title_stack;
+        self.working_directory_host = working_directory_host;
+        self.working_directory = working_directory;
+        self.hyperlinks = hyperlinks;
+        self.hyperlink_prune_len = hyperlink_prune_len;
     }
 
     // csi codes
@@ -1127,6 +2044,11 @@ impl Screen {
         });
     }
 
+    // CSI I
+    pub(crate) fn cht(&mut self, count: u16) {
+        self.grid_mut().col_tab(count);
+    }
+
     // CSI J
     pub(crate) fn ed(&mut self, mode: u16) {
         let attrs = self.attrs;
@@ -1194,20 +2116,49 @@ impl Screen {
         self.grid_mut().erase_cells(count, attrs);
     }
 
+    // CSI Z
+    pub(crate) fn cbt(&mut self, count: u16) {
+        self.grid_mut().col_backtab(count);
+    }
+
     // CSI d
     pub(crate) fn vpa(&mut self, row: u16) {
         self.grid_mut().row_set(row - 1);
     }
 
+    // CSI g
+    pub(crate) fn tbc(&mut self, mode: u16) {
+        match mode {
+            0 => self.grid_mut().clear_tab(),
+            3 => self.grid_mut().clear_all_tabs(),
+            n => {
+                log::debug!("unhandled TBC mode: {n}");
+            }
+        }
+    }
+
     // CSI h
-    #[allow(clippy::unused_self)]
     pub(crate) fn sm(&mut self, params: &vte::Params) {
-        // nothing, i think?
-        if log::log_enabled!(log::Level::Debug) {
-            log::debug!(
-                "unhandled SM mode: {}",
-                crate::perform::param_str(params)
-            );
+        for param in params {
+            match param {
+                &[4] => self.set_mode(MODE_INSERT),
+                &[20] => self.set_mode(MODE_NEWLINE),
+                ns => {
+                    if log::log_enabled!(log::Level::Debug) {
+                        let n = if ns.len() == 1 {
+                            format!(
+                                "{}",
+                                // we just checked that ns.len() == 1, so 0
+                                // must be valid
+                                ns[0]
+                            )
+                        } else {
+                            format!("{ns:?}")
+                        };
+                        log::debug!("unhandled SM mode: {n}");
+                    }
+                }
+            }
         }
     }
 
@@ -1217,28 +2168,45 @@ impl Screen {
             match param {
                 &[1] => self.set_mode(MODE_APPLICATION_CURSOR),
                 &[6] => self.grid_mut().set_origin_mode(true),
+                &[7] => self.clear_mode(MODE_NO_AUTOWRAP),
                 &[9] => self.set_mouse_mode(MouseProtocolMode::Press),
                 &[25] => self.clear_mode(MODE_HIDE_CURSOR),
                 &[47] => self.enter_alternate_grid(),
                 &[1000] => {
                     self.set_mouse_mode(MouseProtocolMode::PressRelease);
                 }
+                &[1001] => self.set_mouse_mode(MouseProtocolMode::Highlight),
                 &[1002] => {
                     self.set_mouse_mode(MouseProtocolMode::ButtonMotion);
                 }
                 &[1003] => self.set_mouse_mode(MouseProtocolMode::AnyMotion),
+                &[1004] => self.set_mode(MODE_FOCUS_REPORTING),
                 &[1005] => {
                     self.set_mouse_encoding(MouseProtocolEncoding::Utf8);
                 }
                 &[1006] => {
                     self.set_mouse_encoding(MouseProtocolEncoding::Sgr);
                 }
+                &[1007] => self.set_mode(MODE_ALTERNATE_SCROLL),
+                &[1015] => {
+                    self.set_mouse_encoding(MouseProtocolEncoding::Urxvt);
+                }
+                &[1016] => {
+                    self.set_mouse_encoding(MouseProtocolEncoding::SgrPixels);
+                }
                 &[1049] => {
                     self.decsc();
                     self.alternate_grid.clear();
                     self.enter_alternate_grid();
                 }
                 &[2004] => self.set_mode(MODE_BRACKETED_PASTE),
+                &[2026] => {
+                    if !self.synchronized_output() {
+                        self.set_mode(MODE_SYNCHRONIZED_OUTPUT);
+                        self.synchronized_output_start =
+                            Some(std::time::Instant::now());
+                    }
+                }
                 ns => {
                     if log::log_enabled!(log::Level::Debug) {
                         let n = if ns.len() == 1 {
@@ -1259,14 +2227,27 @@ impl Screen {
     }
 
     // CSI l
-    #[allow(clippy::unused_self)]
     pub(crate) fn rm(&mut self, params: &vte::Params) {
-        // nothing, i think?
-        if log::log_enabled!(log::Level::Debug) {
-            log::debug!(
-                "unhandled RM mode: {}",
-                crate::perform::param_str(params)
-            );
+        for param in params {
+            match param {
+                &[4] => self.clear_mode(MODE_INSERT),
+                &[20] => self.clear_mode(MODE_NEWLINE),
+                ns => {
+                    if log::log_enabled!(log::Level::Debug) {
+                        let n = if ns.len() == 1 {
+                            format!(
+                                "{}",
+                                // we just checked that ns.len() == 1, so 0
+                                // must be valid
+                                ns[0]
+                            )
+                        } else {
+                            format!("{ns:?}")
+                        };
+                        log::debug!("unhandled RM mode: {n}");
+                    }
+                }
+            }
         }
     }
 
@@ -1276,6 +2257,7 @@ impl Screen {
             match param {
                 &[1] => self.clear_mode(MODE_APPLICATION_CURSOR),
                 &[6] => self.grid_mut().set_origin_mode(false),
+                &[7] => self.set_mode(MODE_NO_AUTOWRAP),
                 &[9] => self.clear_mouse_mode(MouseProtocolMode::Press),
                 &[25] => self.set_mode(MODE_HIDE_CURSOR),
                 &[47] => {
@@ -1284,23 +2266,37 @@ impl Screen {
                 &[1000] => {
                     self.clear_mouse_mode(MouseProtocolMode::PressRelease);
                 }
+                &[1001] => {
+                    self.clear_mouse_mode(MouseProtocolMode::Highlight);
+                }
                 &[1002] => {
                     self.clear_mouse_mode(MouseProtocolMode::ButtonMotion);
                 }
                 &[1003] => {
                     self.clear_mouse_mode(MouseProtocolMode::AnyMotion);
                 }
+                &[1004] => self.clear_mode(MODE_FOCUS_REPORTING),
                 &[1005] => {
                     self.clear_mouse_encoding(MouseProtocolEncoding::Utf8);
                 }
                 &[1006] => {
                     self.clear_mouse_encoding(MouseProtocolEncoding::Sgr);
                 }
+                &[1007] => self.clear_mode(MODE_ALTERNATE_SCROLL),
+                &[1015] => {
+                    self.clear_mouse_encoding(MouseProtocolEncoding::Urxvt);
+                }
+                &[1016] => {
+                    self.clear_mouse_encoding(
+                        MouseProtocolEncoding::SgrPixels,
+                    );
+                }
                 &[1049] => {
                     self.exit_alternate_grid();
                     self.decrc();
                 }
                 &[2004] => self.clear_mode(MODE_BRACKETED_PASTE),
+                &[2026] => self.end_synchronized_output(),
                 ns => {
                     if log::log_enabled!(log::Level::Debug) {
                         let n = if ns.len() == 1 {
@@ -1326,7 +2322,7 @@ impl Screen {
         // instance with a 0 in it, but vte doesn't allow creating new Params
         // instances
         if params.is_empty() {
-            self.attrs = crate::attrs::Attrs::default();
+            self.attrs.reset_sgr();
             return;
         }
 
@@ -1351,109 +2347,81 @@ impl Screen {
             };
         }
 
-        macro_rules! next_param_u8 {
-            () => {
-                if let &[n] = next_param!() {
-                    to_u8!(n)
-                } else {
-                    return;
-                }
-            };
-        }
-
         loop {
             match next_param!() {
-                &[0] => self.attrs = crate::attrs::Attrs::default(),
+                &[0] => self.attrs.reset_sgr(),
                 &[1] => self.attrs.set_bold(true),
+                &[2] => self.attrs.set_dim(true),
                 &[3] => self.attrs.set_italic(true),
                 &[4] => self.attrs.set_underline(true),
+                &[5] | &[6] => self.attrs.set_blink(true),
                 &[7] => self.attrs.set_inverse(true),
-                &[22] => self.attrs.set_bold(false),
+                &[8] => self.attrs.set_hidden(true),
+                &[9] => self.attrs.set_strikethrough(true),
+                &[4, 0] => {
+                    self.attrs.underline_style = crate::UnderlineStyle::None;
+                }
+                &[4, 1] => {
+                    self.attrs.underline_style =
+                        crate::UnderlineStyle::Single;
+                }
+                &[4, 2] | &[21] => {
+                    self.attrs.underline_style =
+                        crate::UnderlineStyle::Double;
+                }
+                &[4, 3] => {
+                    self.attrs.underline_style = crate::UnderlineStyle::Curly;
+                }
+                &[4, 4] => {
+                    self.attrs.underline_style =
+                        crate::UnderlineStyle::Dotted;
+                }
+                &[4, 5] => {
+                    self.attrs.underline_style =
+                        crate::UnderlineStyle::Dashed;
+                }
+                &[22] => {
+                    self.attrs.set_bold(false);
+                    self.attrs.set_dim(false);
+                }
                 &[23] => self.attrs.set_italic(false),
                 &[24] => self.attrs.set_underline(false),
+                &[25] => self.attrs.set_blink(false),
                 &[27] => self.attrs.set_inverse(false),
+                &[28] => self.attrs.set_hidden(false),
+                &[29] => self.attrs.set_strikethrough(false),
                 &[n] if (30..=37).contains(&n) => {
                     self.attrs.fgcolor = crate::Color::Idx(to_u8!(n) - 30);
                 }
-                &[38, 2, r, g, b] => {
-                    self.attrs.fgcolor =
-                        crate::Color::Rgb(to_u8!(r), to_u8!(g), to_u8!(b));
-                }
-                &[38, 5, i] => {
-                    self.attrs.fgcolor = crate::Color::Idx(to_u8!(i));
-                }
-                &[38] => match next_param!() {
-                    &[2] => {
-                        let r = next_param_u8!();
-                        let g = next_param_u8!();
-                        let b = next_param_u8!();
-                        self.attrs.fgcolor = crate::Color::Rgb(r, g, b);
-                    }
-                    &[5] => {
-                        self.attrs.fgcolor =
-                            crate::Color::Idx(next_param_u8!());
-                    }
-                    ns => {
-                        if log::log_enabled!(log::Level::Debug) {
-                            let n = if ns.len() == 1 {
-                                format!(
-                                    "{}",
-                                    // we just checked that ns.len() == 1, so
-                                    // 0 must be valid
-                                    ns[0]
-                                )
-                            } else {
-                                format!("{ns:?}")
-                            };
-                            log::debug!("unhandled SGR mode: 38 {n}");
-                        }
-                        return;
+                param @ &[38, ..] => {
+                    if let Some(color) = sgr_color(param, &mut iter) {
+                        self.attrs.fgcolor = color;
                     }
-                },
+                }
                 &[39] => {
                     self.attrs.fgcolor = crate::Color::Default;
                 }
                 &[n] if (40..=47).contains(&n) => {
                     self.attrs.bgcolor = crate::Color::Idx(to_u8!(n) - 40);
                 }
-                &[48, 2, r, g, b] => {
-                    self.attrs.bgcolor =
-                        crate::Color::Rgb(to_u8!(r), to_u8!(g), to_u8!(b));
-                }
-                &[48, 5, i] => {
-                    self.attrs.bgcolor = crate::Color::Idx(to_u8!(i));
-                }
-                &[48] => match next_param!() {
-                    &[2] => {
-                        let r = next_param_u8!();
-                        let g = next_param_u8!();
-                        let b = next_param_u8!();
-                        self.attrs.bgcolor = crate::Color::Rgb(r, g, b);
-                    }
-                    &[5] => {
-                        self.attrs.bgcolor =
-                            crate::Color::Idx(next_param_u8!());
+                param @ &[48, ..] => {
+                    if let Some(color) = sgr_color(param, &mut iter) {
+                        self.attrs.bgcolor = color;
                     }
-                    ns => {
-                        if log::log_enabled!(log::Level::Debug) {
-                            let n = if ns.len() == 1 {
-                                format!(
-                                    "{}",
-                                    // we just checked that ns.len() == 1, so
-                                    // 0 must be valid
-                                    ns[0]
-                                )
-                            } else {
-                                format!("{ns:?}")
-                            };
-                            log::debug!("unhandled SGR mode: 48 {n}");
-                        }
-                        return;
-                    }
-                },
+                }
                 &[49] => {
                     self.attrs.bgcolor = crate::Color::Default;
                 }
+                &[53] => self.attrs.set_overline(true),
+                &[55] => self.attrs.set_overline(false),
+                param @ &[58, ..] => {
+                    if let Some(color) = sgr_color(param, &mut iter) {
+                        self.attrs.ulcolor = color;
+                    }
+                }
+                &[59] => {
+                    self.attrs.ulcolor = crate::Color::Default;
+                }
                 &[n] if (90..=97).contains(&n) => {
                     self.attrs.fgcolor = crate::Color::Idx(to_u8!(n) - 82);
                 }
@@ -1485,12 +2453,37 @@ impl Screen {
     }
 
     // CSI t
-    #[allow(clippy::unused_self)]
-    pub(crate) fn xtwinops(&self, params: &vte::Params) {
+    pub(crate) fn xtwinops(&mut self, params: &vte::Params) {
         let mut iter = params.iter();
         let op = iter.next().and_then(|x| x.first().copied());
-        match op {
-            Some(8) => {}
+        let selector = iter.next().and_then(|x| x.first().copied());
+        match (op, selector) {
+            (Some(8), _) => {}
+            (Some(22), None | Some(0..=2)) => {
+                let selector = selector.unwrap_or(0);
+                if self.title_stack.len() >= MAX_TITLE_STACK_LEN {
+                    self.title_stack.remove(0);
+                }
+                self.title_stack.push(TitleStackEntry {
+                    icon_name: (selector != 2)
+                        .then(|| self.icon_name.clone()),
+                    title: (selector != 1).then(|| self.title.clone()),
+                });
+            }
+            (Some(23), None | Some(0..=2)) => {
+                let selector = selector.unwrap_or(0);
+                if let Some(entry) = self.title_stack.pop() {
+                    if let Some(icon_name) =
+                        entry.icon_name.filter(|_| selector != 2)
+                    {
+                        self.icon_name = icon_name;
+                    }
+                    if let Some(title) = entry.title.filter(|_| selector != 1)
+                    {
+                        self.title = title;
+                    }
+                }
+            }
             _ => {
                 log::debug!(
                     "unhandled XTWINOPS: {}",
@@ -1500,6 +2493,146 @@ impl Screen {
         }
     }
 
+    // CSI $ p
+    pub(crate) fn rqm(&mut self, mode: u16) {
+        crate::term::ReportMode::new(mode, false, self.ansi_mode_state(mode))
+            .write_buf(&mut self.replies);
+    }
+
+    // CSI ? $ p
+    pub(crate) fn decrqm(&mut self, mode: u16) {
+        crate::term::ReportMode::new(mode, true, self.dec_mode_state(mode))
+            .write_buf(&mut self.replies);
+    }
+
+    // CSI > u
+    pub(crate) fn push_kitty_keyboard_flags(&mut self, flags: u16) {
+        // masking the flags ensures that they fit in a u8
+        let flags =
+            u8::try_from(flags & u16::from(KITTY_KEYBOARD_FLAGS_MASK))
+                .unwrap();
+        let stack = self.kitty_keyboard_stack_mut();
+        if stack.len() >= MAX_KITTY_KEYBOARD_STACK_LEN {
+            stack.remove(0);
+        }
+        stack.push(flags);
+    }
+
+    // CSI < u
+    pub(crate) fn pop_kitty_keyboard_flags(&mut self, count: u16) {
+        let stack = self.kitty_keyboard_stack_mut();
+        stack.truncate(stack.len().saturating_sub(usize::from(count)));
+    }
+
+    // CSI = u
+    pub(crate) fn set_kitty_keyboard_flags(
+        &mut self,
+        (flags, mode): (u16, u16),
+    ) {
+        // masking the flags ensures that they fit in a u8
+        let flags =
+            u8::try_from(flags & u16::from(KITTY_KEYBOARD_FLAGS_MASK))
+                .unwrap();
+        let current = self.kitty_keyboard_flags();
+        let flags = match mode {
+            1 => flags,
+            2 => current | flags,
+            3 => current & !flags,
+            _ => {
+                log::debug!("unhandled kitty keyboard flags mode: {mode}");
+                return;
+            }
+        };
+        let stack = self.kitty_keyboard_stack_mut();
+        if let Some(last) = stack.last_mut() {
+            *last = flags;
+        } else {
+            stack.push(flags);
+        }
+    }
+
+    // CSI ? u
+    pub(crate) fn query_kitty_keyboard_flags(&mut self) {
+        crate::term::ReportKittyKeyboardFlags::new(
+            self.kitty_keyboard_flags(),
+        )
+        .write_buf(&mut self.replies);
+    }
+
+    // CSI SP q
+    pub(crate) fn decscusr(&mut self, style: u16) {
+        if style <= 6 {
+            self.cursor_style = style;
+        } else {
+            log::debug!("unhandled DECSCUSR style: {style}");
+        }
+    }
+
+    // CSI ' z
+    pub(crate) fn decelr(&mut self, mode: u16) {
+        match mode {
+            0 => self.clear_mouse_mode(MouseProtocolMode::DecLocator),
+            1 | 2 => self.set_mouse_mode(MouseProtocolMode::DecLocator),
+            _ => log::debug!("unhandled DECELR mode: {mode}"),
+        }
+    }
+
+    // CSI c
+    pub(crate) fn da(&mut self, attrs: &[u16]) {
+        crate::term::PrimaryDeviceAttributes::new(attrs)
+            .write_buf(&mut self.replies);
+    }
+
+    // CSI > c
+    pub(crate) fn secondary_da(
+        &mut self,
+        (terminal_type, version): (u16, u16),
+    ) {
+        crate::term::SecondaryDeviceAttributes::new(terminal_type, version)
+            .write_buf(&mut self.replies);
+    }
+
+    // CSI n
+    pub(crate) fn dsr(&mut self, params: &vte::Params) {
+        match params.iter().next().and_then(|x| x.first().copied()) {
+            Some(5) => {
+                crate::term::DeviceStatusOk.write_buf(&mut self.replies);
+            }
+            Some(6) => {
+                crate::term::CursorPositionReport::new(
+                    self.grid().reported_pos(),
+                    false,
+                )
+                .write_buf(&mut self.replies);
+            }
+            _ => {
+                log::debug!(
+                    "unhandled DSR: {}",
+                    crate::perform::param_str(params)
+                );
+            }
+        }
+    }
+
+    // CSI ? n
+    pub(crate) fn decdsr(&mut self, params: &vte::Params) {
+        match params.iter().next().and_then(|x| x.first().copied()) {
+            Some(6) => {
+                crate::term::CursorPositionReport::new(
+                    self.grid().reported_pos(),
+                    true,
+                )
+                .write_buf(&mut self.replies);
+            }
+            _ => {
+                log::debug!(
+                    "unhandled DECDSR: {}",
+                    crate::perform::param_str(params)
+                );
+            }
+        }
+    }
+
     // osc codes
 
     pub(crate) fn osc0(&mut self, s: &[u8]) {
@@ -1518,6 +2651,408 @@ impl Screen {
             self.title = s.to_string();
         }
     }
+
+    pub(crate) fn osc133(&mut self, params: &[&[u8]]) {
+        let mark = &mut self.semantic_prompt;
+        match params.first().copied() {
+            Some(b"A" | b"N") => {
+                mark.zone = crate::attrs::SemanticZone::Prompt;
+                mark.prompt_start = true;
+            }
+            // secondary prompts (for line continuations and such) are part
+            // of the prompt which is already being drawn
+            Some(b"P") => {
+                mark.zone = crate::attrs::SemanticZone::Prompt;
+            }
+            Some(b"B") => {
+                mark.zone = crate::attrs::SemanticZone::Input;
+                mark.prompt_start = false;
+                mark.exit_status = None;
+            }
+            Some(b"C") => {
+                mark.zone = crate::attrs::SemanticZone::Output;
+                mark.prompt_start = false;
+                mark.exit_status = None;
+            }
+            Some(b"D") => {
+                mark.zone = crate::attrs::SemanticZone::None;
+                mark.prompt_start = false;
+                mark.exit_status = params
+                    .get(1)
+                    .and_then(|status| std::str::from_utf8(status).ok())
+                    .and_then(|status| status.parse().ok());
+            }
+            _ => {
+                log::debug!(
+                    "unhandled semantic prompt mark: {}",
+                    params
+                        .iter()
+                        .map(|param| String::from_utf8_lossy(param))
+                        .collect::<Vec<_>>()
+                        .join(";")
+                );
+                return;
+            }
+        }
+        self.semantic_prompt_pending = true;
+    }
+
+    pub(crate) fn osc7(&mut self, uri: &[u8]) {
+        // kitty uses its own scheme for this, but the format is the same
+        let Some(rest) = uri
+            .strip_prefix(b"file://")
+            .or_else(|| uri.strip_prefix(b"kitty-shell-cwd://"))
+        else {
+            log::debug!(
+                "unhandled working directory uri: {}",
+                String::from_utf8_lossy(uri)
+            );
+            return;
+        };
+        let (host, path) = rest
+            .iter()
+            .position(|&c| c == b'/')
+            .map_or((rest, &b"/"[..]), |i| rest.split_at(i));
+        let (Ok(host), Some(Ok(path))) = (
+            std::str::from_utf8(host),
+            percent_decode(path).map(String::from_utf8),
+        ) else {
+            log::debug!(
+                "invalid working directory uri: {}",
+                String::from_utf8_lossy(uri)
+            );
+            return;
+        };
+        self.working_directory_host =
+            (!host.is_empty()).then(|| host.to_string());
+        self.working_directory = Some(path);
+    }
+
+    pub(crate) fn osc8(&mut self, params: &[u8], uri: &[u8]) {
+        if uri.is_empty() {
+            self.attrs.hyperlink = None;
+            return;
+        }
+        let (Ok(params), Ok(uri)) =
+            (std::str::from_utf8(params), std::str::from_utf8(uri))
+        else {
+            log::debug!("invalid utf8 in hyperlink");
+            return;
+        };
+        let id = params
+            .split(':')
+            .find_map(|param| param.strip_prefix("id="))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+        self.attrs.hyperlink = Some(
+            self.intern_hyperlink(crate::Hyperlink::new(uri.to_string(), id)),
+        );
+    }
+
+    pub(crate) fn osc4(&mut self, params: &[&[u8]], bel_terminated: bool) {
+        for pair in params.chunks(2) {
+            let &[idx, spec] = pair else {
+                log::debug!("missing color specification for OSC 4");
+                break;
+            };
+            let Some(idx) = std::str::from_utf8(idx)
+                .ok()
+                .and_then(|idx| idx.parse::<u8>().ok())
+            else {
+                log::debug!(
+                    "invalid palette index for OSC 4: {}",
+                    String::from_utf8_lossy(idx)
+                );
+                continue;
+            };
+            if spec == b"?" {
+                // we can't know what colors the real terminal uses, so
+                // assume the xterm defaults
+                let rgb = self
+                    .palette_color(idx)
+                    .unwrap_or_else(|| crate::palette::default_color(idx));
+                crate::term::OscColor::new(4, Some(idx), rgb, bel_terminated)
+                    .write_buf(&mut self.replies);
+            } else if let Some(rgb) = crate::palette::parse_color_spec(spec) {
+                self.palette.colors.insert(idx, rgb);
+            } else {
+                log::debug!(
+                    "unhandled color specification for OSC 4: {}",
+                    String::from_utf8_lossy(spec)
+                );
+            }
+        }
+    }
+
+    // OSC 10, OSC 11, OSC 12
+    pub(crate) fn osc_dynamic_color(
+        &mut self,
+        code: u16,
+        specs: &[&[u8]],
+        bel_terminated: bool,
+        defaults: &[Option<(u8, u8, u8)>; 3],
+    ) {
+        // additional specifications apply to the following dynamic colors,
+        // so OSC 10 ; fg ; bg sets both the foreground and background
+        for (code, spec) in (code..=12).zip(specs) {
+            if *spec == b"?" {
+                // unlike the palette, there is no reasonable default to
+                // report for these, so only answer if we know the value or
+                // were configured with one
+                if let Some(rgb) = self
+                    .palette
+                    .dynamic(code)
+                    .or_else(|| defaults[usize::from(code - 10)])
+                {
+                    crate::term::OscColor::new(
+                        code,
+                        None,
+                        rgb,
+                        bel_terminated,
+                    )
+                    .write_buf(&mut self.replies);
+                }
+            } else if let Some(rgb) = crate::palette::parse_color_spec(spec) {
+                self.palette.set_dynamic(code, Some(rgb));
+            } else {
+                log::debug!(
+                    "unhandled color specification for OSC {code}: {}",
+                    String::from_utf8_lossy(spec)
+                );
+            }
+        }
+    }
+
+    pub(crate) fn osc104(&mut self, params: &[&[u8]]) {
+        if params.iter().all(|idx| idx.is_empty()) {
+            self.palette.colors.clear();
+            return;
+        }
+        for idx in params {
+            if let Some(idx) = std::str::from_utf8(idx)
+                .ok()
+                .and_then(|idx| idx.parse::<u8>().ok())
+            {
+                self.palette.colors.remove(&idx);
+            } else {
+                log::debug!(
+                    "invalid palette index for OSC 104: {}",
+                    String::from_utf8_lossy(idx)
+                );
+            }
+        }
+    }
+
+    // OSC 110, OSC 111, OSC 112
+    pub(crate) fn osc_reset_dynamic_color(&mut self, code: u16) {
+        self.palette.set_dynamic(code - 100, None);
+    }
+
+    // dcs codes
+
+    pub(crate) fn dcs_hook(&mut self, request: Option<DcsRequest>) {
+        self.dcs = request.map(|request| (request, vec![]));
+    }
+
+    pub(crate) fn dcs_put(&mut self, b: u8) {
+        let Some((_, data)) = &mut self.dcs else {
+            return;
+        };
+        if data.len() >= MAX_DCS_LEN {
+            log::debug!("dcs request too long");
+            self.dcs = None;
+            return;
+        }
+        data.push(b);
+    }
+
+    pub(crate) fn take_dcs(&mut self) -> Option<(DcsRequest, Vec<u8>)> {
+        self.dcs.take()
+    }
+
+    pub(crate) fn dcs(
+        &mut self,
+        request: DcsRequest,
+        data: &[u8],
+        capabilities: &std::collections::HashMap<String, String>,
+    ) {
+        match request {
+            DcsRequest::Decrqss => self.decrqss(data),
+            DcsRequest::Xtgettcap => self.xtgettcap(data, capabilities),
+        }
+    }
+
+    // DCS $ q
+    fn decrqss(&mut self, setting: &[u8]) {
+        let report = match setting {
+            b"m" => {
+                let mut default = self.attrs;
+                default.reset_sgr();
+                let mut sgr = vec![];
+                self.attrs.write_escape_code_diff(
+                    &mut sgr,
+                    &default,
+                    &self.hyperlinks,
+                );
+                // the diff from the default attributes is a single SGR
+                // sequence, if anything
+                let params = sgr
+                    .strip_prefix(b"\x1b[")
+                    .and_then(|sgr| sgr.strip_suffix(b"m"))
+                    .unwrap_or_default();
+                let mut report = b"0".to_vec();
+                if !params.is_empty() {
+                    report.push(b';');
+                    report.extend_from_slice(params);
+                }
+                report.push(b'm');
+                Some(report)
+            }
+            b"r" => {
+                let (top, bottom) = self.grid().scroll_region();
+                Some(format!("{};{}r", top + 1, bottom + 1).into_bytes())
+            }
+            // styles 0 and 1 are both a blinking block
+            b" q" => {
+                Some(format!("{} q", self.cursor_style.max(1)).into_bytes())
+            }
+            _ => {
+                log::debug!(
+                    "unhandled DECRQSS setting: {}",
+                    String::from_utf8_lossy(setting)
+                );
+                None
+            }
+        };
+        crate::term::RequestStatusReport::new(report.as_deref())
+            .write_buf(&mut self.replies);
+    }
+
+    // DCS + q
+    fn xtgettcap(
+        &mut self,
+        names: &[u8],
+        capabilities: &std::collections::HashMap<String, String>,
+    ) {
+        for name in names.split(|&b| b == b';') {
+            let value = hex_decode(name)
+                .and_then(|name| String::from_utf8(name).ok())
+                .and_then(|name| capabilities.get(&name));
+            crate::term::CapabilityReport::new(
+                name,
+                value.map(String::as_str),
+            )
+            .write_buf(&mut self.replies);
+        }
+    }
+}
+
+fn percent_decode(s: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut iter = s.iter();
+    while let Some(&c) = iter.next() {
+        if c == b'%' {
+            let hi = char::from(*iter.next()?).to_digit(16)?;
+            let lo = char::from(*iter.next()?).to_digit(16)?;
+            // two hex digits always fit in a u8
+            decoded.push(u8::try_from(hi * 16 + lo).unwrap());
+        } else {
+            decoded.push(c);
+        }
+    }
+    Some(decoded)
+}
+
+fn hex_decode(s: &[u8]) -> Option<Vec<u8>> {
+    s.chunks(2)
+        .map(|digits| {
+            // an odd number of digits leaves a single digit at the end
+            let &[hi, lo] = digits else {
+                return None;
+            };
+            let hi = char::from(hi).to_digit(16)?;
+            let lo = char::from(lo).to_digit(16)?;
+            // two hex digits always fit in a u8
+            Some(u8::try_from(hi * 16 + lo).unwrap())
+        })
+        .collect()
+}
+
+// parses the color specification for an extended color SGR code (38, 48,
+// or 58). this is either in the colon separated form from ITU T.416, where
+// the entire specification is in a single parameter (and may or may not
+// include a color space id), or in the semicolon separated form, where it
+// takes up the following parameters.
+fn sgr_color<'a>(
+    param: &[u16],
+    iter: &mut impl Iterator<Item = &'a [u16]>,
+) -> Option<crate::Color> {
+    let color = match param {
+        &[_] => {
+            let selector = iter.next();
+            // all of the components are consumed even if some of them are
+            // invalid, so that they aren't treated as separate SGR codes
+            let mut next = || match iter.next() {
+                Some(&[n]) => Some(n),
+                _ => None,
+            };
+            match selector {
+                Some(&[2]) => {
+                    let r = next().and_then(u16_to_u8);
+                    let g = next().and_then(u16_to_u8);
+                    let b = next().and_then(u16_to_u8);
+                    r.zip(g)
+                        .zip(b)
+                        .map(|((r, g), b)| crate::Color::Rgb(r, g, b))
+                }
+                Some(&[3]) => match (next(), next(), next()) {
+                    (Some(c), Some(m), Some(y)) => cmyk_to_rgb(c, m, y, 0),
+                    _ => None,
+                },
+                Some(&[4]) => match (next(), next(), next(), next()) {
+                    (Some(c), Some(m), Some(y), Some(k)) => {
+                        cmyk_to_rgb(c, m, y, k)
+                    }
+                    _ => None,
+                },
+                Some(&[5]) => {
+                    next().and_then(u16_to_u8).map(crate::Color::Idx)
+                }
+                _ => None,
+            }
+        }
+        &[_, 2, r, g, b] | &[_, 2, _, r, g, b, ..] => {
+            match (u16_to_u8(r), u16_to_u8(g), u16_to_u8(b)) {
+                (Some(r), Some(g), Some(b)) => {
+                    Some(crate::Color::Rgb(r, g, b))
+                }
+                _ => None,
+            }
+        }
+        &[_, 3, c, m, y] | &[_, 3, _, c, m, y, ..] => cmyk_to_rgb(c, m, y, 0),
+        &[_, 4, c, m, y, k] | &[_, 4, _, c, m, y, k, ..] => {
+            cmyk_to_rgb(c, m, y, k)
+        }
+        &[_, 5, i, ..] => u16_to_u8(i).map(crate::Color::Idx),
+        _ => None,
+    };
+    if color.is_none() && log::log_enabled!(log::Level::Debug) {
+        log::debug!("unhandled SGR color: {param:?}");
+    }
+    color
+}
+
+fn cmyk_to_rgb(c: u16, m: u16, y: u16, k: u16) -> Option<crate::Color> {
+    let max = u16::from(u8::MAX);
+    if c > max || m > max || y > max || k > max {
+        return None;
+    }
+    // values are at most 255 * 255 / 255, so they always fit in a u8
+    let component = |n: u16| {
+        u8::try_from(u32::from(max - n) * u32::from(max - k) / u32::from(max))
+            .unwrap()
+    };
+    Some(crate::Color::Rgb(component(c), component(m), component(y)))
 }
 
 fn u16_to_u8(i: u16) -> Option<u8> {
@@ -34,6 +34,16 @@ impl BufWrite for Crlf {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct CarriageReturn;
+
+impl BufWrite for CarriageReturn {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.push(b'\r');
+    }
+}
+
 #[derive(Default, Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct Backspace;
@@ -94,6 +104,93 @@ impl BufWrite for MoveTo {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct MoveToCol {
+    col: u16,
+}
+
+impl MoveToCol {
+    pub fn new(col: u16) -> Self {
+        Self { col }
+    }
+}
+
+impl BufWrite for MoveToCol {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.col == 0 {
+            buf.extend_from_slice(b"\x1b[G");
+        } else {
+            buf.extend_from_slice(b"\x1b[");
+            extend_itoa(buf, self.col + 1);
+            buf.push(b'G');
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SetTabStop;
+
+impl BufWrite for SetTabStop {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1bH");
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ClearTabStops;
+
+impl BufWrite for ClearTabStops {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[3g");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct DesignateCharset {
+    slot: usize,
+    charset: crate::charset::Charset,
+}
+
+impl DesignateCharset {
+    pub fn new(slot: usize, charset: crate::charset::Charset) -> Self {
+        Self { slot, charset }
+    }
+}
+
+impl BufWrite for DesignateCharset {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.push(b'\x1b');
+        buf.push(b"()*+"[self.slot]);
+        buf.push(self.charset.designator());
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct LockingShift {
+    gl: usize,
+}
+
+impl LockingShift {
+    pub fn new(gl: usize) -> Self {
+        Self { gl }
+    }
+}
+
+impl BufWrite for LockingShift {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.gl == 0 {
+            buf.push(b'\x0f');
+        } else {
+            buf.push(b'\x0e');
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct ClearAttrs;
@@ -110,9 +207,15 @@ pub struct Attrs {
     fgcolor: Option<crate::Color>,
     bgcolor: Option<crate::Color>,
     bold: Option<bool>,
+    dim: Option<bool>,
     italic: Option<bool>,
-    underline: Option<bool>,
+    underline_style: Option<crate::UnderlineStyle>,
+    ulcolor: Option<crate::Color>,
+    blink: Option<bool>,
     inverse: Option<bool>,
+    hidden: Option<bool>,
+    strikethrough: Option<bool>,
+    overline: Option<bool>,
 }
 
 impl Attrs {
@@ -136,8 +239,16 @@ impl Attrs {
         self
     }
 
-    pub fn underline(mut self, underline: bool) -> Self {
-        self.underline = Some(underline);
+    pub fn underline_style(
+        mut self,
+        underline_style: crate::UnderlineStyle,
+    ) -> Self {
+        self.underline_style = Some(underline_style);
+        self
+    }
+
+    pub fn ulcolor(mut self, ulcolor: crate::Color) -> Self {
+        self.ulcolor = Some(ulcolor);
         self
     }
 
@@ -145,6 +256,31 @@ impl Attrs {
         self.inverse = Some(inverse);
         self
     }
+
+    pub fn dim(mut self, dim: bool) -> Self {
+        self.dim = Some(dim);
+        self
+    }
+
+    pub fn blink(mut self, blink: bool) -> Self {
+        self.blink = Some(blink);
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = Some(hidden);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn overline(mut self, overline: bool) -> Self {
+        self.overline = Some(overline);
+        self
+    }
 }
 
 impl BufWrite for Attrs {
@@ -154,9 +290,15 @@ impl BufWrite for Attrs {
         if self.fgcolor.is_none()
             && self.bgcolor.is_none()
             && self.bold.is_none()
+            && self.dim.is_none()
             && self.italic.is_none()
-            && self.underline.is_none()
+            && self.underline_style.is_none()
+            && self.ulcolor.is_none()
+            && self.blink.is_none()
             && self.inverse.is_none()
+            && self.hidden.is_none()
+            && self.strikethrough.is_none()
+            && self.overline.is_none()
         {
             return;
         }
@@ -175,6 +317,13 @@ impl BufWrite for Attrs {
             };
         }
 
+        macro_rules! write_subparam {
+            ($i:expr) => {
+                buf.push(b':');
+                extend_itoa(buf, $i);
+            };
+        }
+
         if let Some(fgcolor) = self.fgcolor {
             match fgcolor {
                 crate::Color::Default => {
@@ -227,12 +376,14 @@ impl BufWrite for Attrs {
             }
         }
 
-        if let Some(bold) = self.bold {
-            if bold {
-                write_param!(1);
-            } else {
-                write_param!(22);
-            }
+        if self.bold == Some(false) || self.dim == Some(false) {
+            write_param!(22);
+        }
+        if self.bold == Some(true) {
+            write_param!(1);
+        }
+        if self.dim == Some(true) {
+            write_param!(2);
         }
 
         if let Some(italic) = self.italic {
@@ -243,11 +394,59 @@ impl BufWrite for Attrs {
             }
         }
 
-        if let Some(underline) = self.underline {
-            if underline {
-                write_param!(4);
+        if let Some(underline_style) = self.underline_style {
+            match underline_style {
+                crate::UnderlineStyle::None => {
+                    write_param!(24);
+                }
+                crate::UnderlineStyle::Single => {
+                    write_param!(4);
+                }
+                crate::UnderlineStyle::Double => {
+                    write_param!(21);
+                }
+                crate::UnderlineStyle::Curly => {
+                    write_param!(4);
+                    write_subparam!(3);
+                }
+                crate::UnderlineStyle::Dotted => {
+                    write_param!(4);
+                    write_subparam!(4);
+                }
+                crate::UnderlineStyle::Dashed => {
+                    write_param!(4);
+                    write_subparam!(5);
+                }
+            }
+        }
+
+        if let Some(ulcolor) = self.ulcolor {
+            match ulcolor {
+                crate::Color::Default => {
+                    write_param!(59);
+                }
+                crate::Color::Idx(i) => {
+                    write_param!(58);
+                    write_subparam!(5);
+                    write_subparam!(i);
+                }
+                crate::Color::Rgb(r, g, b) => {
+                    write_param!(58);
+                    write_subparam!(2);
+                    // empty color space id
+                    buf.push(b':');
+                    write_subparam!(r);
+                    write_subparam!(g);
+                    write_subparam!(b);
+                }
+            }
+        }
+
+        if let Some(blink) = self.blink {
+            if blink {
+                write_param!(5);
             } else {
-                write_param!(24);
+                write_param!(25);
             }
         }
 
@@ -259,201 +458,587 @@ impl BufWrite for Attrs {
             }
         }
 
+        if let Some(hidden) = self.hidden {
+            if hidden {
+                write_param!(8);
+            } else {
+                write_param!(28);
+            }
+        }
+
+        if let Some(strikethrough) = self.strikethrough {
+            if strikethrough {
+                write_param!(9);
+            } else {
+                write_param!(29);
+            }
+        }
+
+        if let Some(overline) = self.overline {
+            if overline {
+                write_param!(53);
+            } else {
+                write_param!(55);
+            }
+        }
+
         buf.push(b'm');
     }
 }
 
 #[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct MoveRight {
-    count: u16,
-}
-
-impl MoveRight {
-    pub fn new(count: u16) -> Self {
-        Self { count }
-    }
+pub struct Hyperlink<'a> {
+    hyperlink: Option<&'a crate::Hyperlink>,
 }
 
-impl Default for MoveRight {
-    fn default() -> Self {
-        Self { count: 1 }
+impl<'a> Hyperlink<'a> {
+    pub fn new(hyperlink: Option<&'a crate::Hyperlink>) -> Self {
+        Self { hyperlink }
     }
 }
 
-impl BufWrite for MoveRight {
+impl BufWrite for Hyperlink<'_> {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        match self.count {
-            0 => {}
-            1 => buf.extend_from_slice(b"\x1b[C"),
-            n => {
-                buf.extend_from_slice(b"\x1b[");
-                extend_itoa(buf, n);
-                buf.push(b'C');
+        buf.extend_from_slice(b"\x1b]8;");
+        if let Some(hyperlink) = self.hyperlink {
+            if let Some(id) = hyperlink.id() {
+                buf.extend_from_slice(b"id=");
+                buf.extend_from_slice(id.as_bytes());
             }
+            buf.push(b';');
+            buf.extend_from_slice(hyperlink.uri().as_bytes());
+        } else {
+            buf.push(b';');
         }
+        buf.push(b'\x07');
     }
 }
 
 #[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct EraseChar {
-    count: u16,
-}
-
-impl EraseChar {
-    pub fn new(count: u16) -> Self {
-        Self { count }
-    }
+pub struct SemanticPrompt {
+    mark: crate::command::Mark,
 }
 
-impl Default for EraseChar {
-    fn default() -> Self {
-        Self { count: 1 }
+impl SemanticPrompt {
+    pub fn new(mark: crate::command::Mark) -> Self {
+        Self { mark }
     }
 }
 
-impl BufWrite for EraseChar {
+impl BufWrite for SemanticPrompt {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        match self.count {
-            0 => {}
-            1 => buf.extend_from_slice(b"\x1b[X"),
-            n => {
-                buf.extend_from_slice(b"\x1b[");
-                extend_itoa(buf, n);
-                buf.push(b'X');
+        // the exit status of the previous command is reported by ending that
+        // command right before the next prompt starts
+        if self.mark.zone == crate::attrs::SemanticZone::None
+            || self.mark.exit_status.is_some()
+        {
+            buf.extend_from_slice(b"\x1b]133;D");
+            if let Some(exit_status) = self.mark.exit_status {
+                buf.push(b';');
+                extend_itoa(buf, exit_status);
+            }
+            buf.push(b'\x07');
+        }
+        match self.mark.zone {
+            crate::attrs::SemanticZone::None => {}
+            crate::attrs::SemanticZone::Prompt => {
+                if self.mark.prompt_start {
+                    buf.extend_from_slice(b"\x1b]133;A\x07");
+                } else {
+                    buf.extend_from_slice(b"\x1b]133;P;k=s\x07");
+                }
+            }
+            crate::attrs::SemanticZone::Input => {
+                buf.extend_from_slice(b"\x1b]133;B\x07");
+            }
+            crate::attrs::SemanticZone::Output => {
+                buf.extend_from_slice(b"\x1b]133;C\x07");
             }
         }
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct HideCursor {
-    state: bool,
+pub struct OscColor {
+    code: u16,
+    idx: Option<u8>,
+    rgb: (u8, u8, u8),
+    bel_terminated: bool,
 }
 
-impl HideCursor {
-    pub fn new(state: bool) -> Self {
-        Self { state }
+impl OscColor {
+    pub fn new(
+        code: u16,
+        idx: Option<u8>,
+        rgb: (u8, u8, u8),
+        bel_terminated: bool,
+    ) -> Self {
+        Self {
+            code,
+            idx,
+            rgb,
+            bel_terminated,
+        }
     }
 }
 
-impl BufWrite for HideCursor {
+impl BufWrite for OscColor {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        if self.state {
-            buf.extend_from_slice(b"\x1b[?25l");
+        buf.extend_from_slice(b"\x1b]");
+        extend_itoa(buf, self.code);
+        if let Some(idx) = self.idx {
+            buf.push(b';');
+            extend_itoa(buf, idx);
+        }
+        buf.extend_from_slice(b";rgb:");
+        for (i, component) in
+            <[u8; 3]>::from(self.rgb).into_iter().enumerate()
+        {
+            if i > 0 {
+                buf.push(b'/');
+            }
+            // use the same four digits per component that xterm uses when
+            // answering color queries
+            extend_hex(buf, component);
+            extend_hex(buf, component);
+        }
+        if self.bel_terminated {
+            buf.push(b'\x07');
         } else {
-            buf.extend_from_slice(b"\x1b[?25h");
+            buf.extend_from_slice(b"\x1b\\");
         }
     }
 }
 
 #[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct MoveFromTo {
-    from: crate::grid::Pos,
-    to: crate::grid::Pos,
+pub struct ResetColor {
+    code: u16,
+    idx: Option<u8>,
 }
 
-impl MoveFromTo {
-    pub fn new(from: crate::grid::Pos, to: crate::grid::Pos) -> Self {
-        Self { from, to }
+impl ResetColor {
+    pub fn new(code: u16, idx: Option<u8>) -> Self {
+        Self { code, idx }
     }
 }
 
-impl BufWrite for MoveFromTo {
+impl BufWrite for ResetColor {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        if self.to.row == self.from.row + 1 && self.to.col == 0 {
-            crate::term::Crlf::default().write_buf(buf);
-        } else if self.from.row == self.to.row && self.from.col < self.to.col
-        {
-            crate::term::MoveRight::new(self.to.col - self.from.col)
-                .write_buf(buf);
-        } else if self.to != self.from {
-            crate::term::MoveTo::new(self.to).write_buf(buf);
+        buf.extend_from_slice(b"\x1b]");
+        extend_itoa(buf, self.code);
+        if let Some(idx) = self.idx {
+            buf.push(b';');
+            extend_itoa(buf, idx);
         }
+        buf.push(b'\x07');
     }
 }
 
+#[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct ChangeTitle<'a> {
-    icon_name: &'a str,
-    title: &'a str,
-    prev_icon_name: &'a str,
-    prev_title: &'a str,
+pub struct Clipboard<'a> {
+    selection: &'a [u8],
+    data: &'a [u8],
+    bel_terminated: bool,
 }
 
-impl<'a> ChangeTitle<'a> {
+impl<'a> Clipboard<'a> {
     pub fn new(
-        icon_name: &'a str,
-        title: &'a str,
-        prev_icon_name: &'a str,
-        prev_title: &'a str,
+        selection: &'a [u8],
+        data: &'a [u8],
+        bel_terminated: bool,
     ) -> Self {
         Self {
-            icon_name,
-            title,
-            prev_icon_name,
-            prev_title,
+            selection,
+            data,
+            bel_terminated,
         }
     }
 }
 
-impl<'a> BufWrite for ChangeTitle<'a> {
+impl BufWrite for Clipboard<'_> {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        if self.icon_name == self.title
-            && (self.icon_name != self.prev_icon_name
-                || self.title != self.prev_title)
-        {
-            buf.extend_from_slice(b"\x1b]0;");
-            buf.extend_from_slice(self.icon_name.as_bytes());
+        buf.extend_from_slice(b"\x1b]52;");
+        buf.extend_from_slice(self.selection);
+        buf.push(b';');
+        buf.extend_from_slice(self.data);
+        if self.bel_terminated {
             buf.push(b'\x07');
         } else {
-            if self.icon_name != self.prev_icon_name {
-                buf.extend_from_slice(b"\x1b]1;");
-                buf.extend_from_slice(self.icon_name.as_bytes());
-                buf.push(b'\x07');
-            }
-            if self.title != self.prev_title {
-                buf.extend_from_slice(b"\x1b]2;");
-                buf.extend_from_slice(self.title.as_bytes());
-                buf.push(b'\x07');
-            }
+            buf.extend_from_slice(b"\x1b\\");
         }
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct ApplicationKeypad {
-    state: bool,
+pub struct PrimaryDeviceAttributes<'a> {
+    attrs: &'a [u16],
 }
 
-impl ApplicationKeypad {
-    pub fn new(state: bool) -> Self {
-        Self { state }
+impl<'a> PrimaryDeviceAttributes<'a> {
+    pub fn new(attrs: &'a [u16]) -> Self {
+        Self { attrs }
     }
 }
 
-impl BufWrite for ApplicationKeypad {
+impl BufWrite for PrimaryDeviceAttributes<'_> {
     fn write_buf(&self, buf: &mut Vec<u8>) {
-        if self.state {
-            buf.extend_from_slice(b"\x1b=");
-        } else {
-            buf.extend_from_slice(b"\x1b>");
+        buf.extend_from_slice(b"\x1b[?");
+        for (i, attr) in self.attrs.iter().enumerate() {
+            if i > 0 {
+                buf.push(b';');
+            }
+            extend_itoa(buf, *attr);
         }
+        buf.push(b'c');
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
-pub struct ApplicationCursor {
-    state: bool,
+pub struct SecondaryDeviceAttributes {
+    terminal_type: u16,
+    version: u16,
 }
 
-impl ApplicationCursor {
-    pub fn new(state: bool) -> Self {
+impl SecondaryDeviceAttributes {
+    pub fn new(terminal_type: u16, version: u16) -> Self {
+        Self {
+            terminal_type,
+            version,
+        }
+    }
+}
+
+impl BufWrite for SecondaryDeviceAttributes {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[>");
+        extend_itoa(buf, self.terminal_type);
+        buf.push(b';');
+        extend_itoa(buf, self.version);
+        buf.extend_from_slice(b";0c");
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct DeviceStatusOk;
+
+impl BufWrite for DeviceStatusOk {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[0n");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct CursorPositionReport {
+    pos: crate::grid::Pos,
+    private: bool,
+}
+
+impl CursorPositionReport {
+    pub fn new(pos: crate::grid::Pos, private: bool) -> Self {
+        Self { pos, private }
+    }
+}
+
+impl BufWrite for CursorPositionReport {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[");
+        if self.private {
+            buf.push(b'?');
+        }
+        extend_itoa(buf, self.pos.row + 1);
+        buf.push(b';');
+        extend_itoa(buf, self.pos.col + 1);
+        if self.private {
+            // the page number, which is always 1
+            buf.extend_from_slice(b";1");
+        }
+        buf.push(b'R');
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ReportMode {
+    mode: u16,
+    private: bool,
+    state: crate::screen::ModeState,
+}
+
+impl ReportMode {
+    pub fn new(
+        mode: u16,
+        private: bool,
+        state: crate::screen::ModeState,
+    ) -> Self {
+        Self {
+            mode,
+            private,
+            state,
+        }
+    }
+}
+
+impl BufWrite for ReportMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[");
+        if self.private {
+            buf.push(b'?');
+        }
+        extend_itoa(buf, self.mode);
+        buf.push(b';');
+        extend_itoa(
+            buf,
+            match self.state {
+                crate::screen::ModeState::NotRecognized => 0,
+                crate::screen::ModeState::Set => 1,
+                crate::screen::ModeState::Reset => 2,
+                crate::screen::ModeState::PermanentlyReset => 4,
+            },
+        );
+        buf.extend_from_slice(b"$y");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct MoveRight {
+    count: u16,
+}
+
+impl MoveRight {
+    pub fn new(count: u16) -> Self {
+        Self { count }
+    }
+}
+
+impl Default for MoveRight {
+    fn default() -> Self {
+        Self { count: 1 }
+    }
+}
+
+impl BufWrite for MoveRight {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        match self.count {
+            0 => {}
+            1 => buf.extend_from_slice(b"\x1b[C"),
+            n => {
+                buf.extend_from_slice(b"\x1b[");
+                extend_itoa(buf, n);
+                buf.push(b'C');
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct EraseChar {
+    count: u16,
+}
+
+impl EraseChar {
+    pub fn new(count: u16) -> Self {
+        Self { count }
+    }
+}
+
+impl Default for EraseChar {
+    fn default() -> Self {
+        Self { count: 1 }
+    }
+}
+
+impl BufWrite for EraseChar {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        match self.count {
+            0 => {}
+            1 => buf.extend_from_slice(b"\x1b[X"),
+            n => {
+                buf.extend_from_slice(b"\x1b[");
+                extend_itoa(buf, n);
+                buf.push(b'X');
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct HideCursor {
+    state: bool,
+}
+
+impl HideCursor {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for HideCursor {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?25l");
+        } else {
+            buf.extend_from_slice(b"\x1b[?25h");
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct MoveFromTo {
+    from: crate::grid::Pos,
+    to: crate::grid::Pos,
+}
+
+impl MoveFromTo {
+    pub fn new(from: crate::grid::Pos, to: crate::grid::Pos) -> Self {
+        Self { from, to }
+    }
+}
+
+impl BufWrite for MoveFromTo {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.to.row == self.from.row + 1 && self.to.col == 0 {
+            crate::term::Crlf::default().write_buf(buf);
+        } else if self.from.row == self.to.row && self.from.col < self.to.col
+        {
+            crate::term::MoveRight::new(self.to.col - self.from.col)
+                .write_buf(buf);
+        } else if self.to != self.from {
+            crate::term::MoveTo::new(self.to).write_buf(buf);
+        }
+    }
+}
+
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ChangeTitle<'a> {
+    icon_name: &'a str,
+    title: &'a str,
+    prev_icon_name: &'a str,
+    prev_title: &'a str,
+}
+
+impl<'a> ChangeTitle<'a> {
+    pub fn new(
+        icon_name: &'a str,
+        title: &'a str,
+        prev_icon_name: &'a str,
+        prev_title: &'a str,
+    ) -> Self {
+        Self {
+            icon_name,
+            title,
+            prev_icon_name,
+            prev_title,
+        }
+    }
+}
+
+impl<'a> BufWrite for ChangeTitle<'a> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.icon_name == self.title
+            && (self.icon_name != self.prev_icon_name
+                || self.title != self.prev_title)
+        {
+            buf.extend_from_slice(b"\x1b]0;");
+            buf.extend_from_slice(self.icon_name.as_bytes());
+            buf.push(b'\x07');
+        } else {
+            if self.icon_name != self.prev_icon_name {
+                buf.extend_from_slice(b"\x1b]1;");
+                buf.extend_from_slice(self.icon_name.as_bytes());
+                buf.push(b'\x07');
+            }
+            if self.title != self.prev_title {
+                buf.extend_from_slice(b"\x1b]2;");
+                buf.extend_from_slice(self.title.as_bytes());
+                buf.push(b'\x07');
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct PushTitle {
+    selector: u16,
+}
+
+impl PushTitle {
+    pub fn new(selector: u16) -> Self {
+        Self { selector }
+    }
+}
+
+impl BufWrite for PushTitle {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[22;");
+        extend_itoa(buf, self.selector);
+        buf.push(b't');
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct PopTitle {
+    selector: u16,
+}
+
+impl PopTitle {
+    pub fn new(selector: u16) -> Self {
+        Self { selector }
+    }
+}
+
+impl BufWrite for PopTitle {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[23;");
+        extend_itoa(buf, self.selector);
+        buf.push(b't');
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ApplicationKeypad {
+    state: bool,
+}
+
+impl ApplicationKeypad {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for ApplicationKeypad {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b=");
+        } else {
+            buf.extend_from_slice(b"\x1b>");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ApplicationCursor {
+    state: bool,
+}
+
+impl ApplicationCursor {
+    pub fn new(state: bool) -> Self {
         Self { state }
     }
 }
@@ -490,6 +1075,329 @@ impl BufWrite for BracketedPaste {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct Autowrap {
+    state: bool,
+}
+
+impl Autowrap {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for Autowrap {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?7h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?7l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct InsertMode {
+    state: bool,
+}
+
+impl InsertMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for InsertMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[4h");
+        } else {
+            buf.extend_from_slice(b"\x1b[4l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct NewlineMode {
+    state: bool,
+}
+
+impl NewlineMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for NewlineMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[20h");
+        } else {
+            buf.extend_from_slice(b"\x1b[20l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct FocusReporting {
+    state: bool,
+}
+
+impl FocusReporting {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for FocusReporting {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?1004h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?1004l");
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct RequestStatusReport<'a> {
+    setting: Option<&'a [u8]>,
+}
+
+impl<'a> RequestStatusReport<'a> {
+    pub fn new(setting: Option<&'a [u8]>) -> Self {
+        Self { setting }
+    }
+}
+
+impl BufWrite for RequestStatusReport<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if let Some(setting) = self.setting {
+            buf.extend_from_slice(b"\x1bP1$r");
+            buf.extend_from_slice(setting);
+        } else {
+            buf.extend_from_slice(b"\x1bP0$r");
+        }
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct CapabilityReport<'a> {
+    hex_name: &'a [u8],
+    value: Option<&'a str>,
+}
+
+impl<'a> CapabilityReport<'a> {
+    pub fn new(hex_name: &'a [u8], value: Option<&'a str>) -> Self {
+        Self { hex_name, value }
+    }
+}
+
+impl BufWrite for CapabilityReport<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.value.is_some() {
+            buf.extend_from_slice(b"\x1bP1+r");
+        } else {
+            buf.extend_from_slice(b"\x1bP0+r");
+        }
+        buf.extend_from_slice(self.hex_name);
+        if let Some(value) = self.value.filter(|value| !value.is_empty()) {
+            buf.push(b'=');
+            for &b in value.as_bytes() {
+                extend_hex(buf, b);
+            }
+        }
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct AlternateScroll {
+    state: bool,
+}
+
+impl AlternateScroll {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for AlternateScroll {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?1007h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?1007l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct FocusEvent {
+    focused: bool,
+}
+
+impl FocusEvent {
+    pub fn new(focused: bool) -> Self {
+        Self { focused }
+    }
+}
+
+impl BufWrite for FocusEvent {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.focused {
+            buf.extend_from_slice(b"\x1b[I");
+        } else {
+            buf.extend_from_slice(b"\x1b[O");
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct KittyKeyboardFlags<'a> {
+    stack: &'a [u8],
+    prev_stack: &'a [u8],
+}
+
+impl<'a> KittyKeyboardFlags<'a> {
+    pub fn new(stack: &'a [u8], prev_stack: &'a [u8]) -> Self {
+        Self { stack, prev_stack }
+    }
+}
+
+impl BufWrite for KittyKeyboardFlags<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        let common = self
+            .stack
+            .iter()
+            .zip(self.prev_stack)
+            .take_while(|(flags, prev_flags)| flags == prev_flags)
+            .count();
+        if self.prev_stack.len() > common {
+            buf.extend_from_slice(b"\x1b[<");
+            extend_itoa(buf, self.prev_stack.len() - common);
+            buf.push(b'u');
+        }
+        for flags in &self.stack[common..] {
+            buf.extend_from_slice(b"\x1b[>");
+            extend_itoa(buf, *flags);
+            buf.push(b'u');
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ReportKittyKeyboardFlags {
+    flags: u8,
+}
+
+impl ReportKittyKeyboardFlags {
+    pub fn new(flags: u8) -> Self {
+        Self { flags }
+    }
+}
+
+impl BufWrite for ReportKittyKeyboardFlags {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[?");
+        extend_itoa(buf, self.flags);
+        buf.push(b'u');
+    }
+}
+
+// a key sent as CSI number ; modifiers final, as xterm does. the kitty
+// keyboard protocol adds an alternate key code after the number, an event
+// type after the modifiers, and the text produced by the key as a list of
+// codepoints.
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct KeyCsi<'a> {
+    number: Option<u32>,
+    alternate: Option<u32>,
+    modifiers: Option<(u16, Option<u8>)>,
+    text: Option<&'a str>,
+    terminator: u8,
+}
+
+impl<'a> KeyCsi<'a> {
+    pub fn new(
+        number: Option<u32>,
+        alternate: Option<u32>,
+        modifiers: Option<(u16, Option<u8>)>,
+        text: Option<&'a str>,
+        terminator: u8,
+    ) -> Self {
+        Self {
+            number,
+            alternate,
+            modifiers,
+            text,
+            terminator,
+        }
+    }
+}
+
+impl BufWrite for KeyCsi<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[");
+        if let Some(number) = self.number {
+            extend_itoa(buf, number);
+        }
+        if let Some(alternate) = self.alternate {
+            buf.push(b':');
+            extend_itoa(buf, alternate);
+        }
+        if let Some((modifiers, event)) = self.modifiers {
+            buf.push(b';');
+            extend_itoa(buf, modifiers);
+            if let Some(event) = event {
+                buf.push(b':');
+                extend_itoa(buf, event);
+            }
+        }
+        if let Some(text) = self.text {
+            buf.push(b';');
+            for (i, c) in text.chars().enumerate() {
+                if i > 0 {
+                    buf.push(b':');
+                }
+                extend_itoa(buf, u32::from(c));
+            }
+        }
+        buf.push(self.terminator);
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct KeySs3 {
+    code: u8,
+}
+
+impl KeySs3 {
+    pub fn new(code: u8) -> Self {
+        Self { code }
+    }
+}
+
+impl BufWrite for KeySs3 {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1bO");
+        buf.push(self.code);
+    }
+}
+
 #[derive(Default, Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct MouseProtocolMode {
@@ -527,6 +1435,12 @@ impl BufWrite for MouseProtocolMode {
                 crate::MouseProtocolMode::AnyMotion => {
                     buf.extend_from_slice(b"\x1b[?1003l");
                 }
+                crate::MouseProtocolMode::Highlight => {
+                    buf.extend_from_slice(b"\x1b[?1001l");
+                }
+                crate::MouseProtocolMode::DecLocator => {
+                    buf.extend_from_slice(b"\x1b[0'z");
+                }
             },
             crate::MouseProtocolMode::Press => {
                 buf.extend_from_slice(b"\x1b[?9h");
@@ -534,6 +1448,12 @@ impl BufWrite for MouseProtocolMode {
             crate::MouseProtocolMode::PressRelease => {
                 buf.extend_from_slice(b"\x1b[?1000h");
             }
+            crate::MouseProtocolMode::Highlight => {
+                buf.extend_from_slice(b"\x1b[?1001h");
+            }
+            crate::MouseProtocolMode::DecLocator => {
+                buf.extend_from_slice(b"\x1b[1'z");
+            }
             crate::MouseProtocolMode::ButtonMotion => {
                 buf.extend_from_slice(b"\x1b[?1002h");
             }
@@ -575,6 +1495,12 @@ impl BufWrite for MouseProtocolEncoding {
                 crate::MouseProtocolEncoding::Sgr => {
                     buf.extend_from_slice(b"\x1b[?1006l");
                 }
+                crate::MouseProtocolEncoding::Urxvt => {
+                    buf.extend_from_slice(b"\x1b[?1015l");
+                }
+                crate::MouseProtocolEncoding::SgrPixels => {
+                    buf.extend_from_slice(b"\x1b[?1016l");
+                }
             },
             crate::MouseProtocolEncoding::Utf8 => {
                 buf.extend_from_slice(b"\x1b[?1005h");
@@ -582,11 +1508,130 @@ impl BufWrite for MouseProtocolEncoding {
             crate::MouseProtocolEncoding::Sgr => {
                 buf.extend_from_slice(b"\x1b[?1006h");
             }
+            crate::MouseProtocolEncoding::Urxvt => {
+                buf.extend_from_slice(b"\x1b[?1015h");
+            }
+            crate::MouseProtocolEncoding::SgrPixels => {
+                buf.extend_from_slice(b"\x1b[?1016h");
+            }
         }
     }
 }
 
+// mouse reports in the default (x10 compatible) encoding, where each value
+// is sent as a single byte
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct MouseReport {
+    code: u8,
+    col: u8,
+    row: u8,
+}
+
+impl MouseReport {
+    pub fn new(code: u8, col: u8, row: u8) -> Self {
+        Self { code, col, row }
+    }
+}
+
+impl BufWrite for MouseReport {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[M");
+        buf.extend_from_slice(&[self.code, self.col, self.row]);
+    }
+}
+
+// mouse reports in the utf-8 encoding, where each value is sent as a
+// utf-8 encoded character
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct Utf8MouseReport {
+    code: char,
+    col: char,
+    row: char,
+}
+
+impl Utf8MouseReport {
+    pub fn new(code: char, col: char, row: char) -> Self {
+        Self { code, col, row }
+    }
+}
+
+impl BufWrite for Utf8MouseReport {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[M");
+        let mut char_buf = [0; 4];
+        for c in [self.code, self.col, self.row] {
+            buf.extend_from_slice(c.encode_utf8(&mut char_buf).as_bytes());
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct UrxvtMouseReport {
+    code: u16,
+    col: u32,
+    row: u32,
+}
+
+impl UrxvtMouseReport {
+    pub fn new(code: u16, col: u32, row: u32) -> Self {
+        Self { code, col, row }
+    }
+}
+
+impl BufWrite for UrxvtMouseReport {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[");
+        extend_itoa(buf, self.code);
+        buf.push(b';');
+        extend_itoa(buf, self.col);
+        buf.push(b';');
+        extend_itoa(buf, self.row);
+        buf.push(b'M');
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SgrMouseReport {
+    code: u16,
+    col: u32,
+    row: u32,
+    release: bool,
+}
+
+impl SgrMouseReport {
+    pub fn new(code: u16, col: u32, row: u32, release: bool) -> Self {
+        Self {
+            code,
+            col,
+            row,
+            release,
+        }
+    }
+}
+
+impl BufWrite for SgrMouseReport {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[<");
+        extend_itoa(buf, self.code);
+        buf.push(b';');
+        extend_itoa(buf, self.col);
+        buf.push(b';');
+        extend_itoa(buf, self.row);
+        buf.push(if self.release { b'm' } else { b'M' });
+    }
+}
+
 fn extend_itoa<I: itoa::Integer>(buf: &mut Vec<u8>, i: I) {
     let mut itoa_buf = itoa::Buffer::new();
     buf.extend_from_slice(itoa_buf.format(i).as_bytes());
 }
+
+fn extend_hex(buf: &mut Vec<u8>, i: u8) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    buf.push(DIGITS[usize::from(i >> 4)]);
+    buf.push(DIGITS[usize::from(i & 0xf)]);
+}
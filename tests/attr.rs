@@ -21,3 +21,226 @@ fn attributes_formatted() {
     parser.process(b"\x1b[m");
     assert_eq!(parser.screen().attributes_formatted(), b"\x1b[m");
 }
+
+#[test]
+fn extended_attrs() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[2ma\x1b[5mb\x1b[6;8mc\x1b[9md\x1b[21me\x1b[53mf");
+    let cell = parser.screen().cell(0, 5).unwrap();
+    assert!(cell.dim());
+    assert!(cell.blink());
+    assert!(cell.hidden());
+    assert!(cell.strikethrough());
+    assert!(cell.double_underline());
+    assert!(cell.underline());
+    assert!(cell.overline());
+    assert!(!cell.bold());
+    assert!(parser.screen().cell(0, 0).unwrap().dim());
+    assert!(!parser.screen().cell(0, 0).unwrap().blink());
+    assert!(parser.screen().cell(0, 2).unwrap().blink());
+    assert!(!parser.screen().cell(0, 3).unwrap().double_underline());
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[2;21;5;8;9;53m"
+    );
+
+    parser.process(b"\x1b[4mg\x1b[1mh");
+    let cell = parser.screen().cell(0, 6).unwrap();
+    assert!(cell.underline());
+    assert!(!cell.double_underline());
+    assert!(parser.screen().cell(0, 7).unwrap().bold());
+    assert!(parser.screen().cell(0, 7).unwrap().dim());
+
+    // bold and dim are reset together
+    parser.process(b"\x1b[22;24mi");
+    let cell = parser.screen().cell(0, 8).unwrap();
+    assert!(!cell.bold());
+    assert!(!cell.dim());
+    assert!(!cell.underline());
+    assert!(!cell.double_underline());
+    assert!(cell.blink());
+
+    parser.process(b"\x1b[25;28;29;55mj");
+    let mut plain = shpool_vt100::Parser::default();
+    plain.process(b"j");
+    assert_eq!(parser.screen().cell(0, 9), plain.screen().cell(0, 0));
+
+    // turning off one of bold or dim has to restore the other
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[1;2;4ma");
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[22;2;24;21mb");
+    assert_eq!(parser.screen().contents_diff(&screen), b"\x1b[22;2;21mb");
+
+    helpers::assert_reproduces_state(
+        b"\x1b[2ma\x1b[5mb\x1b[6;8mc\x1b[9md\x1b[21me\x1b[53mf\x1b[1;4mg",
+    );
+    helpers::assert_reproduces_state_from(
+        b"\x1b[22;1;24;4;25mh\x1b[28;29;55mi",
+        b"\x1b[2ma\x1b[5mb\x1b[6;8mc\x1b[9md\x1b[21me\x1b[53mf",
+    );
+}
+
+#[test]
+fn underline_styles() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"a\x1b[4mb\x1b[4:2mc\x1b[4:3md\x1b[4:4me\x1b[4:5mf");
+    parser.process(b"\x1b[21mg\x1b[4:1mh\x1b[4:0mi\x1b[4:3mj\x1b[24mk");
+    let styles: Vec<_> = (0..11)
+        .map(|col| parser.screen().cell(0, col).unwrap().underline_style())
+        .collect();
+    assert_eq!(
+        styles,
+        vec![
+            shpool_vt100::UnderlineStyle::None,
+            shpool_vt100::UnderlineStyle::Single,
+            shpool_vt100::UnderlineStyle::Double,
+            shpool_vt100::UnderlineStyle::Curly,
+            shpool_vt100::UnderlineStyle::Dotted,
+            shpool_vt100::UnderlineStyle::Dashed,
+            shpool_vt100::UnderlineStyle::Double,
+            shpool_vt100::UnderlineStyle::Single,
+            shpool_vt100::UnderlineStyle::None,
+            shpool_vt100::UnderlineStyle::Curly,
+            shpool_vt100::UnderlineStyle::None,
+        ]
+    );
+    assert!(parser.screen().cell(0, 3).unwrap().underline());
+    assert!(!parser.screen().cell(0, 3).unwrap().double_underline());
+
+    helpers::assert_reproduces_state(
+        b"a\x1b[4mb\x1b[4:2mc\x1b[4:3md\x1b[4:4me\x1b[4:5mf\x1b[21mg",
+    );
+    helpers::assert_reproduces_state_from(
+        b"\x1b[Hx\x1b[4:3my\x1b[4:5m",
+        b"a\x1b[4mb\x1b[4:2mc\x1b[4:3md\x1b[4:4me\x1b[4:5mf\x1b[21mg",
+    );
+}
+
+#[test]
+fn underline_color() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"a\x1b[58:5:1mb\x1b[58:2::1:2:3mc\x1b[58:2:4:5:6md");
+    parser.process(b"\x1b[58;5;7me\x1b[58;2;8;9;10mf\x1b[59mg");
+    let colors: Vec<_> = (0..7)
+        .map(|col| parser.screen().cell(0, col).unwrap().underline_color())
+        .collect();
+    assert_eq!(
+        colors,
+        vec![
+            shpool_vt100::Color::Default,
+            shpool_vt100::Color::Idx(1),
+            shpool_vt100::Color::Rgb(1, 2, 3),
+            shpool_vt100::Color::Rgb(4, 5, 6),
+            shpool_vt100::Color::Idx(7),
+            shpool_vt100::Color::Rgb(8, 9, 10),
+            shpool_vt100::Color::Default,
+        ]
+    );
+
+    // the underline color is independent of the underline style
+    parser.process(b"\x1b[4:3;58:2::255:0:0m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[4:3;58:2::255:0:0m"
+    );
+    parser.process(b"\x1b[24m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[58:2::255:0:0m"
+    );
+
+    helpers::assert_reproduces_state(
+        b"a\x1b[58:5:1mb\x1b[58:2::1:2:3mc\x1b[4:3;58;5;7md",
+    );
+    helpers::assert_reproduces_state_from(
+        b"\x1b[Hx\x1b[59my\x1b[58:5:200m",
+        b"a\x1b[58:5:1mb\x1b[58:2::1:2:3mc\x1b[4:3;58;5;7md",
+    );
+}
+
+#[test]
+fn t416_colors() {
+    fn fgcolor(input: &[u8]) -> shpool_vt100::Color {
+        let mut parser = shpool_vt100::Parser::default();
+        parser.process(input);
+        parser.process(b"a");
+        parser.screen().cell(0, 0).unwrap().fgcolor()
+    }
+
+    let rgb = shpool_vt100::Color::Rgb(1, 2, 3);
+    assert_eq!(fgcolor(b"\x1b[38;2;1;2;3m"), rgb);
+    assert_eq!(fgcolor(b"\x1b[38:2:1:2:3m"), rgb);
+    assert_eq!(fgcolor(b"\x1b[38:2::1:2:3m"), rgb);
+    assert_eq!(fgcolor(b"\x1b[38:2:0:1:2:3m"), rgb);
+    assert_eq!(fgcolor(b"\x1b[38:2::1:2:3::0:0m"), rgb);
+    assert_eq!(fgcolor(b"\x1b[38;5;100m"), shpool_vt100::Color::Idx(100));
+    assert_eq!(fgcolor(b"\x1b[38:5:100m"), shpool_vt100::Color::Idx(100));
+
+    assert_eq!(
+        fgcolor(b"\x1b[38:3::255:0:51m"),
+        shpool_vt100::Color::Rgb(0, 255, 204)
+    );
+    assert_eq!(
+        fgcolor(b"\x1b[38:3:255:0:51m"),
+        shpool_vt100::Color::Rgb(0, 255, 204)
+    );
+    assert_eq!(
+        fgcolor(b"\x1b[38:4::255:0:51:0m"),
+        shpool_vt100::Color::Rgb(0, 255, 204)
+    );
+    assert_eq!(
+        fgcolor(b"\x1b[38:4:0:0:0:51m"),
+        shpool_vt100::Color::Rgb(204, 204, 204)
+    );
+    assert_eq!(
+        fgcolor(b"\x1b[38:4:1:255:255:255:0m"),
+        shpool_vt100::Color::Rgb(0, 0, 0)
+    );
+
+    // the same forms work for the background and underline colors
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[48:2::1:2:3;58:4::255:0:51:0ma");
+    let cell = parser.screen().cell(0, 0).unwrap();
+    assert_eq!(cell.bgcolor(), rgb);
+    assert_eq!(
+        cell.underline_color(),
+        shpool_vt100::Color::Rgb(0, 255, 204)
+    );
+    parser.process(b"\x1b[48:3::255:0:51;58:2:0:1:2:3mb");
+    let cell = parser.screen().cell(0, 1).unwrap();
+    assert_eq!(cell.bgcolor(), shpool_vt100::Color::Rgb(0, 255, 204));
+    assert_eq!(cell.underline_color(), rgb);
+
+    // invalid or unknown color specifications don't discard the rest of the
+    // sequence
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[38:9:1;1;48:2::300:0:0;3;58:3:1;4;38;2;1;2;3ma");
+    let cell = parser.screen().cell(0, 0).unwrap();
+    assert!(cell.bold());
+    assert!(cell.italic());
+    assert!(cell.underline());
+    assert_eq!(cell.fgcolor(), rgb);
+    assert_eq!(cell.bgcolor(), shpool_vt100::Color::Default);
+    assert_eq!(cell.underline_color(), shpool_vt100::Color::Default);
+
+    // the semicolon separated forms consume all of their components, so
+    // they aren't treated as separate codes
+    assert_eq!(
+        fgcolor(b"\x1b[38;3;255;0;51m"),
+        shpool_vt100::Color::Rgb(0, 255, 204)
+    );
+    assert_eq!(
+        fgcolor(b"\x1b[38;4;0;0;0;51m"),
+        shpool_vt100::Color::Rgb(204, 204, 204)
+    );
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[38;3;1;2;3;48;4;1;2;3;300;7ma");
+    let cell = parser.screen().cell(0, 0).unwrap();
+    assert!(!cell.bold());
+    assert!(!cell.dim());
+    assert!(!cell.italic());
+    assert!(cell.inverse());
+    assert_eq!(cell.fgcolor(), shpool_vt100::Color::Rgb(254, 253, 252));
+    assert_eq!(cell.bgcolor(), shpool_vt100::Color::Default);
+}
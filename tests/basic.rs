@@ -50,10 +50,15 @@ fn set_size() {
     assert_eq!(parser.screen().contents(), "01234567890123456789");
 
     parser.screen_mut().set_size(24, 80);
-    assert_eq!(parser.screen().contents(), "01234567\n89012345\n6789");
+    assert_eq!(parser.screen().contents(), "01234567890123456789");
+    assert_eq!(parser.screen().cursor_position(), (0, 20));
 
     parser.screen_mut().set_size(34, 8);
-    assert_eq!(parser.screen().contents(), "01234567\n89012345\n6789");
+    assert_eq!(parser.screen().contents(), "01234567890123456789");
+    assert_eq!(parser.screen().cursor_position(), (2, 4));
+    assert!(parser.screen().row_wrapped(0));
+    assert!(parser.screen().row_wrapped(1));
+    assert!(!parser.screen().row_wrapped(2));
 
     let mut parser = shpool_vt100::Parser::default();
     assert_eq!(parser.screen().size(), (24, 80));
@@ -70,6 +75,75 @@ fn set_size() {
     assert_eq!(parser.screen().contents(), "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n21\n22\n23\n24                                                                                                foobar");
 }
 
+#[test]
+fn set_size_reflow() {
+    let mut parser = shpool_vt100::Parser::new(4, 10, 10);
+    parser.process(b"$ echo aaaaaaaaaaaaaaa\r\naaaaaaaaaaaaaaa\r\n$ ");
+    assert_eq!(parser.screen().contents(), "aa\naaaaaaaaaaaaaaa\n$ ");
+    assert_eq!(parser.screen().cursor_position(), (3, 2));
+
+    // shrinking pushes the rewrapped rows into the scrollback
+    parser.screen_mut().set_size(4, 5);
+    assert_eq!(parser.screen().contents(), "aaaaaaaaaaaaaaa\n$ ");
+    assert_eq!(parser.screen().cursor_position(), (3, 2));
+    parser.screen_mut().set_scrollback(4);
+    assert_eq!(parser.screen().contents(), "o aaaaaaaaaaaaaaa");
+    parser.screen_mut().set_scrollback(0);
+
+    // and growing pulls them back out
+    parser.screen_mut().set_size(4, 30);
+    assert_eq!(
+        parser.screen().contents(),
+        "$ echo aaaaaaaaaaaaaaa\naaaaaaaaaaaaaaa\n$ "
+    );
+    assert_eq!(parser.screen().cursor_position(), (2, 2));
+    assert!(!parser.screen().row_wrapped(0));
+    assert!(!parser.screen().row_wrapped(1));
+
+    // the cursor stays on the same character
+    let mut parser = shpool_vt100::Parser::new(24, 10, 0);
+    parser.process(b"abcdefghijklmnopqrstuvwxyz\x1b[2;4H");
+    assert_eq!(parser.screen().cursor_position(), (1, 3));
+    parser.screen_mut().set_size(24, 6);
+    assert_eq!(parser.screen().cursor_position(), (2, 1));
+    assert_eq!(parser.screen().cell(2, 1).unwrap().contents(), "n");
+    parser.screen_mut().set_size(24, 80);
+    assert_eq!(parser.screen().cursor_position(), (0, 13));
+    assert_eq!(parser.screen().contents(), "abcdefghijklmnopqrstuvwxyz");
+
+    // wide characters are never split across rows
+    let mut parser = shpool_vt100::Parser::new(24, 80, 0);
+    parser.process("a\u{3042}\u{3044}\u{3046}".as_bytes());
+    parser.screen_mut().set_size(24, 4);
+    assert_eq!(parser.screen().contents(), "a\u{3042}\n\u{3044}\u{3046}");
+    assert!(!parser.screen().row_wrapped(0));
+    assert!(parser.screen().cell(1, 0).unwrap().is_wide());
+    assert_eq!(parser.screen().cursor_position(), (1, 4));
+
+    // rows which are filled with a background color stay filled
+    let mut parser = shpool_vt100::Parser::new(24, 10, 0);
+    parser.process(b"\x1b[41m\x1b[Kfoo\x1b[m\r\n");
+    parser.screen_mut().set_size(24, 20);
+    assert_eq!(parser.screen().contents(), "foo");
+    assert_eq!(
+        parser.screen().cell(0, 19).unwrap().bgcolor(),
+        shpool_vt100::Color::Idx(1)
+    );
+    assert!(!parser.screen().row_wrapped(0));
+    assert_eq!(parser.screen().cursor_position(), (1, 0));
+
+    // the saved cursor is moved along with the text, but the alternate
+    // screen is left for the application to redraw
+    let mut parser = shpool_vt100::Parser::new(5, 10, 0);
+    parser.process(b"0123456789abcdef\r\n\x1b[?1049hABCDEFGHIJKL");
+    parser.screen_mut().set_size(5, 5);
+    assert_eq!(parser.screen().contents(), "ABCDE\nKL");
+    assert!(!parser.screen().row_wrapped(0));
+    parser.process(b"\x1b[?1049l");
+    assert_eq!(parser.screen().contents(), "0123456789abcdef");
+    assert_eq!(parser.screen().cursor_position(), (4, 0));
+}
+
 #[test]
 fn cell_contents() {
     let mut parser = shpool_vt100::Parser::default();
@@ -71,3 +71,58 @@ fn ff() {
 fn cr() {
     helpers::fixture("cr");
 }
+
+#[test]
+fn tab_stops() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 8));
+
+    // HTS and TBC
+    parser.process(b"\x1b[3g\x1b[5G\x1bH\x1b[13G\x1bH\x1b[21G\x1bH\r");
+    parser.process(b"\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 4));
+    parser.process(b"\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 12));
+    parser.process(b"\x1b[g\r\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 4));
+    parser.process(b"\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 20));
+    parser.process(b"\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 79));
+
+    // CHT and CBT
+    parser.process(b"\r\x1b[2I");
+    assert_eq!(parser.screen().cursor_position(), (0, 20));
+    parser.process(b"\x1b[Z");
+    assert_eq!(parser.screen().cursor_position(), (0, 4));
+    parser.process(b"\x1b[Z");
+    assert_eq!(parser.screen().cursor_position(), (0, 0));
+    parser.process(b"\x1b[70G\x1b[5Z");
+    assert_eq!(parser.screen().cursor_position(), (0, 0));
+
+    // the formatted output reproduces the tab stops
+    let mut new_parser = shpool_vt100::Parser::default();
+    new_parser.process(&parser.screen().state_formatted());
+    new_parser.process(b"\t\t");
+    assert_eq!(new_parser.screen().cursor_position(), (0, 20));
+
+    let mut new_parser = shpool_vt100::Parser::default();
+    let prev_screen = new_parser.screen().clone();
+    new_parser.process(&parser.screen().state_diff(&prev_screen));
+    new_parser.process(b"\t\t");
+    assert_eq!(new_parser.screen().cursor_position(), (0, 20));
+
+    // widening the screen adds the default tab stops to the new columns
+    parser.screen_mut().set_size(24, 100);
+    parser.process(b"\x1b[81G\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 88));
+
+    // RIS resets everything
+    parser.process(b"\x1bc\t\t");
+    assert_eq!(parser.screen().cursor_position(), (0, 16));
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        b"\x1b[?25h\x1b[m\x1b[H\x1b[J\x1b[16C"
+    );
+}
@@ -78,9 +78,63 @@ fn xtwinops() {
         b"\x1b[H\x1b[8;24;15tbbbbbbbbbbbbbbbbbbbb\x1b[8;24;80tcccccccccccccccccccc",
         &mut Callbacks,
     );
-    assert_eq!(vt.screen().rows(0, 80).next().unwrap(), "bbbbbbbbbbbbbbb");
+    assert_eq!(vt.screen().rows(0, 80).next().unwrap(), "bbbbbbbbbbbbbbbbbbbbccccccccccccccccccccaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+    assert_eq!(vt.screen().rows(0, 80).nth(1).unwrap(), "aaaaaaaaaa");
+}
+
+#[test]
+fn device_status() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[5n"), b"\x1b[0n");
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[6n"), b"\x1b[1;1R");
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[5;10Hab\x1b[6n"),
+        b"\x1b[5;12R"
+    );
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[?6n"), b"\x1b[?5;12;1R");
+
+    // a pending wrap still reports the last column
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[5;79Hab\x1b[6n"),
+        b"\x1b[5;80R"
+    );
+
+    // origin mode reports positions relative to the scroll region
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[5;20r\x1b[?6h\x1b[3;4H\x1b[6n"),
+        b"\x1b[3;4R"
+    );
     assert_eq!(
-        vt.screen().rows(0, 80).nth(1).unwrap(),
-        "bbbbbcccccccccccccccccccc"
+        helpers::replies(&mut parser, b"\x1b[?6l\x1b[7;4H\x1b[6n"),
+        b"\x1b[7;4R"
     );
+
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[7n"), b"");
+    parser.process(b"\x1b[6n");
+    assert_eq!(helpers::replies(&mut parser, b""), b"");
+}
+
+#[test]
+fn device_attributes() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[c"), b"\x1b[?1;2c");
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[0c"), b"\x1b[?1;2c");
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[>c"), b"\x1b[>0;0;0c");
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[1c"), b"");
+
+    parser.set_primary_device_attributes(&[62, 22]);
+    parser.set_secondary_device_attributes(41, 390);
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[c"), b"\x1b[?62;22c");
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[>0c"),
+        b"\x1b[>41;390;0c"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[c\x1b[5n"),
+        b"\x1b[?62;22c\x1b[0n"
+    );
+
+    // replies are discarded when there are no callbacks to receive them
+    parser.process(b"\x1b[c\x1b[>c");
+    assert_eq!(helpers::replies(&mut parser, b""), b"");
 }
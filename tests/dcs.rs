@@ -0,0 +1,105 @@
+mod helpers;
+
+#[test]
+fn decrqss() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP$qm\x1b\\"),
+        b"\x1bP1$r0m\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[1;4;31;48;5;100m\x1bP$qm\x1b\\"),
+        b"\x1bP1$r0;31;48;5;100;1;4m\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[m\x1bP$qm\x1b\\"),
+        b"\x1bP1$r0m\x1b\\"
+    );
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP$qr\x1b\\"),
+        b"\x1bP1$r1;24r\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[5;20r\x1bP$qr\x1b\\"),
+        b"\x1bP1$r5;20r\x1b\\"
+    );
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP$q q\x1b\\"),
+        b"\x1bP1$r1 q\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[6 q\x1bP$q q\x1b\\"),
+        b"\x1bP1$r6 q\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[7 q\x1bP$q q\x1b\\"),
+        b"\x1bP1$r6 q\x1b\\"
+    );
+
+    // character protection (DECSCA) isn't supported
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[1\"q\x1bP$q\"q\x1b\\"),
+        b"\x1bP0$r\x1b\\"
+    );
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP$qx\x1b\\"),
+        b"\x1bP0$r\x1b\\"
+    );
+
+    // the request can be split across calls
+    assert_eq!(helpers::replies(&mut parser, b"\x1bP$q"), b"");
+    assert_eq!(
+        helpers::replies(&mut parser, b"r\x1b\\"),
+        b"\x1bP1$r5;20r\x1b\\"
+    );
+
+    // and the state is reset by ris
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bc\x1bP$q q\x1b\\"),
+        b"\x1bP1$r1 q\x1b\\"
+    );
+
+    // other dcs sequences are ignored
+    assert_eq!(helpers::replies(&mut parser, b"\x1bPqm\x1b\\"), b"");
+    parser.process(b"\x1bP$qm\x1b\\");
+    assert_eq!(helpers::replies(&mut parser, b""), b"");
+}
+
+#[test]
+fn xtgettcap() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP+q436f\x1b\\"),
+        b"\x1bP1+r436f=323536\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP+q524742;544e;zz\x1b\\"),
+        b"\x1bP1+r524742\x1b\\\x1bP0+r544e\x1b\\\x1bP0+rzz\x1b\\"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP+q436;436f6\x1b\\"),
+        b"\x1bP0+r436\x1b\\\x1bP0+r436f6\x1b\\"
+    );
+
+    parser.set_terminal_capabilities(&[
+        ("TN", "shpool"),
+        ("Ss", "\x1b[%p1%d q"),
+    ]);
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1bP+q544e;5373;436f\x1b\\"),
+        b"\x1bP1+r544e=7368706f6f6c\x1b\\\
+          \x1bP1+r5373=1b5b25703125642071\x1b\\\
+          \x1bP0+r436f\x1b\\"
+    );
+
+    // overly long requests are ignored
+    let mut input = b"\x1bP+q".to_vec();
+    input.extend(b"544e;".repeat(1000));
+    input.extend(b"\x1b\\");
+    assert_eq!(helpers::replies(&mut parser, &input), b"");
+    parser.process(b"\x1bP+q544e\x1b\\");
+    assert_eq!(helpers::replies(&mut parser, b""), b"");
+}
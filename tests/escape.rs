@@ -77,3 +77,45 @@ fn restore_out_of_bounds_pos() {
     // restore cursor
     parser.process(b"\x1b8");
 }
+
+#[test]
+fn charsets() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b(0lqqk\x1b(B lqqk");
+    assert_eq!(parser.screen().contents(), "┌──┐ lqqk");
+
+    parser.process(b"\r\n\x1b)0a\x0eaxa\x0fa");
+    assert_eq!(parser.screen().contents(), "┌──┐ lqqk\na▒│▒a");
+
+    parser.process(b"\r\n\x1b*A\x1b+0#\x1bN#\x1bO`#");
+    assert_eq!(parser.screen().contents(), "┌──┐ lqqk\na▒│▒a\n#£◆#");
+
+    parser.process(b"\r\n\x1b(A\x1b7\x1b(B\x1b8#");
+    assert_eq!(parser.screen().contents(), "┌──┐ lqqk\na▒│▒a\n#£◆#\n£");
+
+    parser.process(b"\x1bc#");
+    assert_eq!(parser.screen().contents(), "#");
+
+    let prev_input = b"\x1b(0\x1b)A\x0eq";
+    let input = b"\x0f\x1b(B\x1b)0qr";
+    helpers::assert_reproduces_state(prev_input);
+    helpers::assert_reproduces_state_from(input, prev_input);
+    helpers::assert_reproduces_state_from(prev_input, input);
+
+    // the character sets themselves should also be reproduced
+    for input in [&prev_input[..], &input[..]] {
+        let mut parser = shpool_vt100::Parser::default();
+        parser.process(input);
+        let mut formatted = shpool_vt100::Parser::default();
+        formatted.process(&parser.screen().state_formatted());
+        let mut diffed = shpool_vt100::Parser::default();
+        diffed.process(b"\x1b(0\x1b+A\x0e#q");
+        let prev_screen = diffed.screen().clone();
+        diffed.process(&parser.screen().state_diff(&prev_screen));
+        for parser in [&mut parser, &mut formatted, &mut diffed] {
+            parser.process(b"\x1b[H#q\x0e#q\x0f#q");
+        }
+        assert_eq!(formatted.screen().contents(), parser.screen().contents());
+        assert_eq!(diffed.screen().contents(), parser.screen().contents());
+    }
+}
@@ -30,6 +30,18 @@ pub struct FixtureCell {
     underline: bool,
     #[serde(default, skip_serializing_if = "is_default")]
     inverse: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    dim: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    blink: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    hidden: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    strikethrough: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    double_underline: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    overline: bool,
 }
 
 impl FixtureCell {
@@ -45,6 +57,12 @@ impl FixtureCell {
             italic: cell.italic(),
             underline: cell.underline(),
             inverse: cell.inverse(),
+            dim: cell.dim(),
+            blink: cell.blink(),
+            hidden: cell.hidden(),
+            strikethrough: cell.strikethrough(),
+            double_underline: cell.double_underline(),
+            overline: cell.overline(),
         }
     }
 }
@@ -178,8 +196,10 @@ where
         "none" => Ok(shpool_vt100::MouseProtocolMode::None),
         "press" => Ok(shpool_vt100::MouseProtocolMode::Press),
         "press_release" => Ok(shpool_vt100::MouseProtocolMode::PressRelease),
+        "highlight" => Ok(shpool_vt100::MouseProtocolMode::Highlight),
         "button_motion" => Ok(shpool_vt100::MouseProtocolMode::ButtonMotion),
         "any_motion" => Ok(shpool_vt100::MouseProtocolMode::AnyMotion),
+        "dec_locator" => Ok(shpool_vt100::MouseProtocolMode::DecLocator),
         _ => unimplemented!(),
     }
 }
@@ -195,8 +215,10 @@ where
         shpool_vt100::MouseProtocolMode::None => "none",
         shpool_vt100::MouseProtocolMode::Press => "press",
         shpool_vt100::MouseProtocolMode::PressRelease => "press_release",
+        shpool_vt100::MouseProtocolMode::Highlight => "highlight",
         shpool_vt100::MouseProtocolMode::ButtonMotion => "button_motion",
         shpool_vt100::MouseProtocolMode::AnyMotion => "any_motion",
+        shpool_vt100::MouseProtocolMode::DecLocator => "dec_locator",
     };
     serializer.serialize_str(s)
 }
@@ -212,6 +234,8 @@ where
         "default" => Ok(shpool_vt100::MouseProtocolEncoding::Default),
         "utf8" => Ok(shpool_vt100::MouseProtocolEncoding::Utf8),
         "sgr" => Ok(shpool_vt100::MouseProtocolEncoding::Sgr),
+        "urxvt" => Ok(shpool_vt100::MouseProtocolEncoding::Urxvt),
+        "sgr_pixels" => Ok(shpool_vt100::MouseProtocolEncoding::SgrPixels),
         _ => unimplemented!(),
     }
 }
@@ -227,6 +251,8 @@ where
         shpool_vt100::MouseProtocolEncoding::Default => "default",
         shpool_vt100::MouseProtocolEncoding::Utf8 => "utf8",
         shpool_vt100::MouseProtocolEncoding::Sgr => "sgr",
+        shpool_vt100::MouseProtocolEncoding::Urxvt => "urxvt",
+        shpool_vt100::MouseProtocolEncoding::SgrPixels => "sgr_pixels",
     };
     serializer.serialize_str(s)
 }
@@ -54,6 +54,56 @@ impl<'a> std::fmt::Debug for Bytes<'a> {
     }
 }
 
+// collects the replies that the terminal sends back to the application
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct Replies(pub Vec<u8>);
+
+impl shpool_vt100::Callbacks for Replies {
+    fn reply(&mut self, _: &mut shpool_vt100::Screen, reply: &[u8]) {
+        self.0.extend_from_slice(reply);
+    }
+}
+
+#[allow(dead_code)]
+pub fn replies(parser: &mut shpool_vt100::Parser, input: &[u8]) -> Vec<u8> {
+    let mut replies = Replies::default();
+    parser.process_cb(input, &mut replies);
+    replies.0
+}
+
+// cells refer to hyperlinks by an id which is local to their screen, so
+// compare everything about them other than that id directly
+fn cell_state(
+    screen: &shpool_vt100::Screen,
+    row: u16,
+    col: u16,
+) -> impl std::fmt::Debug + PartialEq + '_ {
+    let cell = screen.cell(row, col).unwrap();
+    (
+        (
+            cell.contents(),
+            cell.is_wide(),
+            cell.is_wide_continuation(),
+            cell.fgcolor(),
+            cell.bgcolor(),
+            cell.underline_style(),
+            cell.underline_color(),
+        ),
+        (
+            cell.bold(),
+            cell.dim(),
+            cell.italic(),
+            cell.inverse(),
+            cell.blink(),
+            cell.hidden(),
+            cell.strikethrough(),
+            cell.overline(),
+        ),
+        screen.hyperlink(cell),
+    )
+}
+
 pub fn compare_screens(
     got: &shpool_vt100::Screen,
     expected: &shpool_vt100::Screen,
@@ -88,11 +138,14 @@ pub fn compare_screens(
 
     for row in 0..rows {
         for col in 0..cols {
-            let expected_cell = expected.cell(row, col);
-            let got_cell = got.cell(row, col);
-            is!(got_cell, expected_cell);
+            is!(cell_state(got, row, col), cell_state(expected, row, col));
+            is!(
+                got.semantic_zone(row, col),
+                expected.semantic_zone(row, col)
+            );
         }
     }
+    is!(got.commands(), expected.commands());
 
     is!(got.cursor_position(), expected.cursor_position());
     ok!(got.cursor_position().0 <= rows);
@@ -103,11 +156,24 @@ pub fn compare_screens(
     is!(got.title(), expected.title());
     is!(got.icon_name(), expected.icon_name());
 
+    for idx in 0..=u8::MAX {
+        is!(got.palette_color(idx), expected.palette_color(idx));
+    }
+    is!(got.foreground_color(), expected.foreground_color());
+    is!(got.background_color(), expected.background_color());
+    is!(got.cursor_color(), expected.cursor_color());
+
     is!(got.application_keypad(), expected.application_keypad());
     is!(got.application_cursor(), expected.application_cursor());
     is!(got.hide_cursor(), expected.hide_cursor());
     is!(got.bracketed_paste(), expected.bracketed_paste());
+    is!(got.autowrap(), expected.autowrap());
+    is!(got.insert_mode(), expected.insert_mode());
+    is!(got.newline_mode(), expected.newline_mode());
+    is!(got.focus_reporting(), expected.focus_reporting());
+    is!(got.kitty_keyboard_flags(), expected.kitty_keyboard_flags());
     is!(got.mouse_protocol_mode(), expected.mouse_protocol_mode());
+    is!(got.alternate_scroll(), expected.alternate_scroll());
     is!(
         got.mouse_protocol_encoding(),
         expected.mouse_protocol_encoding()
@@ -132,6 +198,7 @@ pub fn contents_formatted_reproduces_screen(screen: &shpool_vt100::Screen) -> bo
     let mut new_input = screen.contents_formatted();
     new_input.extend(screen.input_mode_formatted());
     new_input.extend(screen.title_formatted());
+    new_input.extend(screen.palette_formatted());
     assert_eq!(new_input, screen.state_formatted());
     let mut new_parser = shpool_vt100::Parser::default();
     new_parser.process(&new_input);
@@ -156,6 +223,7 @@ pub fn rows_formatted_reproduces_screen(screen: &shpool_vt100::Screen) -> bool {
     new_input.extend(screen.attributes_formatted());
     new_input.extend(screen.input_mode_formatted());
     new_input.extend(screen.title_formatted());
+    new_input.extend(screen.palette_formatted());
     let mut new_parser = shpool_vt100::Parser::default();
     new_parser.process(&new_input);
     let got_screen = new_parser.screen().clone();
@@ -195,11 +263,13 @@ pub fn contents_diff_reproduces_state_from_screens(
     let mut diff_input = screen.contents_diff(prev_screen);
     diff_input.extend(screen.input_mode_diff(prev_screen));
     diff_input.extend(screen.title_diff(prev_screen));
+    diff_input.extend(screen.palette_diff(prev_screen));
     assert_eq!(diff_input, screen.state_diff(prev_screen));
 
     let mut diff_prev_input = prev_screen.contents_formatted();
-    diff_prev_input.extend(screen.input_mode_formatted());
-    diff_prev_input.extend(screen.title_formatted());
+    diff_prev_input.extend(prev_screen.input_mode_formatted());
+    diff_prev_input.extend(prev_screen.title_formatted());
+    diff_prev_input.extend(prev_screen.palette_formatted());
 
     let mut new_parser = shpool_vt100::Parser::default();
     new_parser.process(&diff_prev_input);
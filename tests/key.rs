@@ -0,0 +1,253 @@
+use shpool_vt100::{Key, KeyEvent, KeyEventKind, Modifiers};
+
+const NONE: Modifiers = Modifiers {
+    shift: false,
+    alt: false,
+    ctrl: false,
+    super_: false,
+};
+const SHIFT: Modifiers = Modifiers {
+    shift: true,
+    ..NONE
+};
+const ALT: Modifiers = Modifiers { alt: true, ..NONE };
+const CTRL: Modifiers = Modifiers { ctrl: true, ..NONE };
+const CTRL_SHIFT: Modifiers = Modifiers {
+    ctrl: true,
+    shift: true,
+    ..NONE
+};
+
+fn key(parser: &shpool_vt100::Parser, key: Key, mods: Modifiers) -> Vec<u8> {
+    parser.screen().key_event(&KeyEvent::new(key, mods))
+}
+
+fn key_kind(
+    parser: &shpool_vt100::Parser,
+    key: Key,
+    mods: Modifiers,
+    kind: KeyEventKind,
+) -> Vec<u8> {
+    let mut event = KeyEvent::new(key, mods);
+    event.kind = kind;
+    parser.screen().key_event(&event)
+}
+
+#[test]
+fn legacy_text() {
+    let parser = shpool_vt100::Parser::default();
+    assert_eq!(key(&parser, Key::Char('a'), NONE), b"a");
+    assert_eq!(key(&parser, Key::Char('a'), SHIFT), b"A");
+    assert_eq!(key(&parser, Key::Char('a'), ALT), b"\x1ba");
+    assert_eq!(key(&parser, Key::Char('a'), CTRL), b"\x01");
+    assert_eq!(
+        key(&parser, Key::Char('a'), Modifiers { alt: true, ..CTRL }),
+        b"\x1b\x01"
+    );
+    assert_eq!(key(&parser, Key::Char('['), CTRL), b"\x1b");
+    assert_eq!(key(&parser, Key::Char(' '), CTRL), b"\x00");
+    assert_eq!(key(&parser, Key::Char('?'), CTRL), b"\x7f");
+    assert_eq!(key(&parser, Key::Char('1'), CTRL), b"1");
+    assert_eq!(key(&parser, Key::Char('é'), NONE), "é".as_bytes());
+
+    let mut event = KeyEvent::new(Key::Char('e'), NONE);
+    event.text = Some("ê".to_string());
+    assert_eq!(parser.screen().key_event(&event), "ê".as_bytes());
+
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), NONE, KeyEventKind::Repeat),
+        b"a"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), NONE, KeyEventKind::Release),
+        b""
+    );
+}
+
+#[test]
+fn legacy_special_keys() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(key(&parser, Key::Enter, NONE), b"\r");
+    assert_eq!(key(&parser, Key::Enter, ALT), b"\x1b\r");
+    assert_eq!(key(&parser, Key::Tab, NONE), b"\t");
+    assert_eq!(key(&parser, Key::Tab, SHIFT), b"\x1b[Z");
+    assert_eq!(key(&parser, Key::Backspace, NONE), b"\x7f");
+    assert_eq!(key(&parser, Key::Backspace, CTRL), b"\x08");
+    assert_eq!(key(&parser, Key::Backspace, ALT), b"\x1b\x7f");
+    assert_eq!(key(&parser, Key::Escape, NONE), b"\x1b");
+
+    parser.process(b"\x1b[20h");
+    assert_eq!(key(&parser, Key::Enter, NONE), b"\r\n");
+    assert_eq!(key(&parser, Key::KeypadEnter, NONE), b"\r\n");
+}
+
+#[test]
+fn legacy_cursor_keys() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(key(&parser, Key::Up, NONE), b"\x1b[A");
+    assert_eq!(key(&parser, Key::Down, NONE), b"\x1b[B");
+    assert_eq!(key(&parser, Key::Right, NONE), b"\x1b[C");
+    assert_eq!(key(&parser, Key::Left, NONE), b"\x1b[D");
+    assert_eq!(key(&parser, Key::Home, NONE), b"\x1b[H");
+    assert_eq!(key(&parser, Key::End, NONE), b"\x1b[F");
+    assert_eq!(key(&parser, Key::Up, CTRL), b"\x1b[1;5A");
+    assert_eq!(key(&parser, Key::Left, CTRL_SHIFT), b"\x1b[1;6D");
+    assert_eq!(key(&parser, Key::End, ALT), b"\x1b[1;3F");
+
+    parser.process(b"\x1b[?1h");
+    assert_eq!(key(&parser, Key::Up, NONE), b"\x1bOA");
+    assert_eq!(key(&parser, Key::Home, NONE), b"\x1bOH");
+    assert_eq!(key(&parser, Key::Up, CTRL), b"\x1b[1;5A");
+}
+
+#[test]
+fn legacy_function_keys() {
+    let parser = shpool_vt100::Parser::default();
+    assert_eq!(key(&parser, Key::Insert, NONE), b"\x1b[2~");
+    assert_eq!(key(&parser, Key::Delete, NONE), b"\x1b[3~");
+    assert_eq!(key(&parser, Key::PageUp, NONE), b"\x1b[5~");
+    assert_eq!(key(&parser, Key::PageDown, SHIFT), b"\x1b[6;2~");
+
+    let expected: [&[u8]; 12] = [
+        b"\x1bOP",
+        b"\x1bOQ",
+        b"\x1bOR",
+        b"\x1bOS",
+        b"\x1b[15~",
+        b"\x1b[17~",
+        b"\x1b[18~",
+        b"\x1b[19~",
+        b"\x1b[20~",
+        b"\x1b[21~",
+        b"\x1b[23~",
+        b"\x1b[24~",
+    ];
+    for (n, expected) in (1..=12).zip(expected) {
+        assert_eq!(key(&parser, Key::F(n), NONE), expected);
+    }
+    assert_eq!(key(&parser, Key::F(1), CTRL), b"\x1b[1;5P");
+    assert_eq!(key(&parser, Key::F(3), SHIFT), b"\x1b[1;2R");
+    assert_eq!(key(&parser, Key::F(5), ALT), b"\x1b[15;3~");
+    assert_eq!(key(&parser, Key::F(13), NONE), b"");
+}
+
+#[test]
+fn legacy_keypad() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(key(&parser, Key::Keypad('5'), NONE), b"5");
+    assert_eq!(key(&parser, Key::Keypad('+'), NONE), b"+");
+    assert_eq!(key(&parser, Key::KeypadEnter, NONE), b"\r");
+
+    parser.process(b"\x1b=");
+    assert_eq!(key(&parser, Key::Keypad('0'), NONE), b"\x1bOp");
+    assert_eq!(key(&parser, Key::Keypad('9'), NONE), b"\x1bOy");
+    assert_eq!(key(&parser, Key::Keypad('.'), NONE), b"\x1bOn");
+    assert_eq!(key(&parser, Key::Keypad('/'), NONE), b"\x1bOo");
+    assert_eq!(key(&parser, Key::Keypad('*'), NONE), b"\x1bOj");
+    assert_eq!(key(&parser, Key::Keypad('-'), NONE), b"\x1bOm");
+    assert_eq!(key(&parser, Key::Keypad('+'), NONE), b"\x1bOk");
+    assert_eq!(key(&parser, Key::Keypad('='), NONE), b"\x1bOX");
+    assert_eq!(key(&parser, Key::KeypadEnter, NONE), b"\x1bOM");
+}
+
+#[test]
+fn kitty_disambiguate() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[>1u");
+    assert_eq!(key(&parser, Key::Char('a'), NONE), b"a");
+    assert_eq!(key(&parser, Key::Char('a'), SHIFT), b"A");
+    assert_eq!(key(&parser, Key::Char('a'), CTRL), b"\x1b[97;5u");
+    assert_eq!(key(&parser, Key::Char('a'), ALT), b"\x1b[97;3u");
+    assert_eq!(key(&parser, Key::Char('a'), CTRL_SHIFT), b"\x1b[97;6u");
+    assert_eq!(key(&parser, Key::Escape, NONE), b"\x1b[27u");
+    assert_eq!(key(&parser, Key::Enter, NONE), b"\r");
+    assert_eq!(key(&parser, Key::Enter, CTRL), b"\x1b[13;5u");
+    assert_eq!(key(&parser, Key::Tab, NONE), b"\t");
+    assert_eq!(key(&parser, Key::Tab, SHIFT), b"\x1b[9;2u");
+    assert_eq!(key(&parser, Key::Backspace, NONE), b"\x7f");
+    assert_eq!(key(&parser, Key::Backspace, ALT), b"\x1b[127;3u");
+    assert_eq!(key(&parser, Key::Up, NONE), b"\x1b[A");
+    assert_eq!(key(&parser, Key::Up, CTRL), b"\x1b[1;5A");
+    assert_eq!(key(&parser, Key::F(1), NONE), b"\x1b[P");
+    assert_eq!(key(&parser, Key::F(3), NONE), b"\x1b[13~");
+    assert_eq!(key(&parser, Key::Delete, NONE), b"\x1b[3~");
+    assert_eq!(key(&parser, Key::Keypad('1'), NONE), b"1");
+    assert_eq!(key(&parser, Key::Keypad('1'), CTRL), b"\x1b[57400;5u");
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), NONE, KeyEventKind::Release),
+        b""
+    );
+
+    // application cursor mode doesn't apply to the kitty protocol
+    parser.process(b"\x1b[?1h");
+    assert_eq!(key(&parser, Key::Up, NONE), b"\x1b[A");
+}
+
+#[test]
+fn kitty_event_types() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[>3u");
+    assert_eq!(key(&parser, Key::Char('a'), NONE), b"a");
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), NONE, KeyEventKind::Repeat),
+        b"a"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), NONE, KeyEventKind::Release),
+        b"\x1b[97;1:3u"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::Char('a'), CTRL, KeyEventKind::Repeat),
+        b"\x1b[97;5:2u"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::Up, NONE, KeyEventKind::Release),
+        b"\x1b[1;1:3A"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::PageUp, SHIFT, KeyEventKind::Repeat),
+        b"\x1b[5;2:2~"
+    );
+    assert_eq!(
+        key_kind(&parser, Key::Enter, NONE, KeyEventKind::Release),
+        b""
+    );
+}
+
+#[test]
+fn kitty_all_keys() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[>8u");
+    assert_eq!(key(&parser, Key::Char('a'), NONE), b"\x1b[97u");
+    assert_eq!(key(&parser, Key::Char('a'), SHIFT), b"\x1b[97;2u");
+    assert_eq!(key(&parser, Key::Enter, NONE), b"\x1b[13u");
+    assert_eq!(key(&parser, Key::Tab, NONE), b"\x1b[9u");
+    assert_eq!(key(&parser, Key::Backspace, NONE), b"\x1b[127u");
+    assert_eq!(key(&parser, Key::KeypadEnter, NONE), b"\x1b[57414u");
+    assert_eq!(key(&parser, Key::Keypad('='), NONE), b"\x1b[57415u");
+    assert_eq!(
+        key_kind(&parser, Key::Enter, NONE, KeyEventKind::Release),
+        b""
+    );
+
+    parser.process(b"\x1b[=14;1u");
+    assert_eq!(
+        key_kind(&parser, Key::Enter, NONE, KeyEventKind::Release),
+        b"\x1b[13;1:3u"
+    );
+
+    // alternate keys
+    assert_eq!(key(&parser, Key::Char('a'), SHIFT), b"\x1b[97:65;2u");
+    assert_eq!(key(&parser, Key::Char('a'), CTRL), b"\x1b[97;5u");
+
+    // associated text
+    parser.process(b"\x1b[=24;1u");
+    assert_eq!(key(&parser, Key::Char('a'), NONE), b"\x1b[97;1;97u");
+    assert_eq!(key(&parser, Key::Char('a'), SHIFT), b"\x1b[97;2;65u");
+    assert_eq!(key(&parser, Key::Char('a'), CTRL), b"\x1b[97;5u");
+    let mut event = KeyEvent::new(Key::Char('e'), NONE);
+    event.text = Some("ê".to_string());
+    assert_eq!(parser.screen().key_event(&event), b"\x1b[101;1;234u");
+    assert_eq!(key(&parser, Key::Escape, NONE), b"\x1b[27u");
+    assert_eq!(key(&parser, Key::Up, NONE), b"\x1b[A");
+}
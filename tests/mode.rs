@@ -9,3 +9,360 @@ fn modes() {
 fn alternate_buffer() {
     helpers::fixture("alternate_buffer");
 }
+
+#[test]
+fn autowrap() {
+    let mut parser = shpool_vt100::Parser::new(3, 10, 0);
+    assert!(parser.screen().autowrap());
+
+    parser.process(b"\x1b[?7l");
+    assert!(!parser.screen().autowrap());
+    parser.process(b"0123456789abc");
+    assert_eq!(parser.screen().contents(), "012345678c");
+    assert_eq!(parser.screen().cursor_position(), (0, 9));
+    assert!(!parser.screen().row_wrapped(0));
+
+    parser.process(b"\x1b[3;1Hxxxxxxxxxyz");
+    assert_eq!(parser.screen().contents(), "012345678c\n\nxxxxxxxxxz");
+    assert_eq!(parser.screen().cursor_position(), (2, 9));
+
+    parser.process(b"\x1b[2;9H\xe3\x82\xad\xe3\x82\xad");
+    assert_eq!(
+        parser.screen().contents(),
+        "012345678c\n        \u{30ad}\nxxxxxxxxxz"
+    );
+    assert_eq!(parser.screen().cursor_position(), (1, 9));
+    helpers::assert_reproduces_state(b"\x1b[?7l0123456789abc");
+
+    parser.process(b"\x1b[?7h");
+    assert!(parser.screen().autowrap());
+    parser.process(b"\x1b[3;1Hxxxxxxxxxyz");
+    assert_eq!(parser.screen().contents(), "        \u{30ad}\nxxxxxxxxxyz");
+
+    assert!(!parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[?7l"));
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"foo\x1b[?7l");
+    assert!(parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[?7l"));
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?7h");
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[?7h");
+    helpers::assert_contents_diff_reproduces_state_from_screens(
+        &screen,
+        parser.screen(),
+    );
+    helpers::assert_reproduces_state_from(
+        &[&b"\x1b[?7h"[..], &[b'a'; 200], b"\x1b[?7l"].concat(),
+        b"\x1b[?7l\x1b[H\x1b[80Gb",
+    );
+}
+
+#[test]
+fn insert_mode() {
+    let mut parser = shpool_vt100::Parser::new(24, 10, 0);
+    assert!(!parser.screen().insert_mode());
+
+    parser.process(b"abcdefghij\x1b[1;3H\x1b[4h");
+    assert!(parser.screen().insert_mode());
+    parser.process(b"XY");
+    assert_eq!(parser.screen().contents(), "abXYcdefgh");
+    assert_eq!(parser.screen().cursor_position(), (0, 4));
+
+    parser.process(b"\x1b[4l");
+    assert!(!parser.screen().insert_mode());
+    parser.process(b"Z");
+    assert_eq!(parser.screen().contents(), "abXYZdefgh");
+
+    // splitting a wide character clears it
+    parser.process(b"\x1b[2H\xe3\x82\xad\xe3\x82\xad\x1b[2;2H\x1b[4hx");
+    assert_eq!(parser.screen().contents(), "abXYZdefgh\n x \u{30ad}");
+    assert!(!parser.screen().cell(1, 0).unwrap().is_wide());
+    assert!(!parser.screen().cell(1, 2).unwrap().is_wide_continuation());
+
+    // wide characters pushed off the end of the row are removed
+    parser.process(b"\x1b[3Habcdefgh\xe3\x82\xad\x1b[3;2Hx");
+    assert_eq!(
+        parser.screen().contents(),
+        "abXYZdefgh\n x \u{30ad}\naxbcdefgh"
+    );
+    assert!(!parser.screen().cell(2, 9).unwrap().is_wide());
+
+    parser.process(b"\x1b[4h");
+    assert!(parser.screen().input_mode_formatted().ends_with(b"\x1b[4h"));
+    helpers::assert_reproduces_state(b"foo\x1b[4h");
+    helpers::assert_reproduces_state_from(b"\x1b[Hbar", b"foo\x1b[4h");
+    helpers::assert_reproduces_state_from(b"\x1b[4lbar", b"foo\x1b[4h");
+}
+
+#[test]
+fn newline_mode() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert!(!parser.screen().newline_mode());
+
+    parser.process(b"foo\nbar\x0bbaz\x0cquux");
+    assert_eq!(
+        parser.screen().contents(),
+        "foo\n   bar\n      baz\n         quux"
+    );
+
+    parser.process(b"\x1b[H\x1b[J\x1b[20h");
+    assert!(parser.screen().newline_mode());
+    parser.process(b"foo\nbar\x0bbaz\x0cquux");
+    assert_eq!(parser.screen().contents(), "foo\nbar\nbaz\nquux");
+    assert_eq!(parser.screen().cursor_position(), (3, 4));
+
+    parser.process(b"\x1b[20l\nfoo");
+    assert!(!parser.screen().newline_mode());
+    assert_eq!(parser.screen().contents(), "foo\nbar\nbaz\nquux\n    foo");
+
+    helpers::assert_reproduces_state(b"\x1b[20hfoo\nbar");
+    helpers::assert_reproduces_state_from(b"\x1b[20l", b"\x1b[20hfoo\nbar");
+
+    // the grid diff moves the cursor with a bare linefeed to restore a
+    // pending wrap, which must not also return the carriage
+    let mut input = b"\x1b[20l".to_vec();
+    input.extend(b"a".repeat(80));
+    input.extend(b"\n\x1b[20h");
+    helpers::assert_reproduces_state_from(&input, b"\x1b[20h");
+}
+
+#[test]
+fn mode_queries() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[4$p"), b"\x1b[4;2$y");
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[4h\x1b[4$p\x1b[20$p"),
+        b"\x1b[4;1$y\x1b[20;2$y"
+    );
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[3$p"), b"\x1b[3;0$y");
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?7$p\x1b[?25$p\x1b[?2004$p"),
+        b"\x1b[?7;1$y\x1b[?25;1$y\x1b[?2004;2$y"
+    );
+    assert_eq!(
+        helpers::replies(
+            &mut parser,
+            b"\x1b[?7l\x1b[?25l\x1b[?2004h\x1b[?7$p\x1b[?25$p\x1b[?2004$p"
+        ),
+        b"\x1b[?7;2$y\x1b[?25;2$y\x1b[?2004;1$y"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?1h\x1b[?6h\x1b[?1$p\x1b[?6$p"),
+        b"\x1b[?1;1$y\x1b[?6;1$y"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?1049h\x1b[?1049$p\x1b[?47$p"),
+        b"\x1b[?1049;1$y\x1b[?47;1$y"
+    );
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?1002h\x1b[?1006h"),
+        b""
+    );
+    assert_eq!(
+        helpers::replies(
+            &mut parser,
+            b"\x1b[?1000$p\x1b[?1002$p\x1b[?1005$p\x1b[?1006$p"
+        ),
+        b"\x1b[?1000;2$y\x1b[?1002;1$y\x1b[?1005;2$y\x1b[?1006;1$y"
+    );
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?2027$p\x1b[?12345$p"),
+        b"\x1b[?2027;4$y\x1b[?12345;0$y"
+    );
+}
+
+#[test]
+fn focus_reporting() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert!(!parser.screen().focus_reporting());
+    assert_eq!(parser.screen().focus_event(true), b"");
+    assert!(!parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[?1004l"));
+
+    parser.process(b"\x1b[?1004h");
+    assert!(parser.screen().focus_reporting());
+    assert_eq!(parser.screen().focus_event(true), b"\x1b[I");
+    assert_eq!(parser.screen().focus_event(false), b"\x1b[O");
+    assert!(parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[?1004h"));
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?1004$p"),
+        b"\x1b[?1004;1$y"
+    );
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1004l");
+    assert!(!parser.screen().focus_reporting());
+    assert_eq!(parser.screen().focus_event(true), b"");
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[?1004l");
+    assert_eq!(screen.input_mode_diff(parser.screen()), b"\x1b[?1004h");
+
+    helpers::assert_reproduces_state(b"\x1b[?1004h");
+    helpers::assert_reproduces_state_from(b"\x1b[?1004l", b"\x1b[?1004h");
+}
+
+#[test]
+fn synchronized_output() {
+    #[derive(Default)]
+    struct Frames(usize);
+
+    impl shpool_vt100::Callbacks for Frames {
+        fn synchronized_output_complete(
+            &mut self,
+            screen: &mut shpool_vt100::Screen,
+        ) {
+            assert!(!screen.synchronized_output());
+            self.0 += 1;
+        }
+    }
+
+    let mut parser = shpool_vt100::Parser::default();
+    let mut frames = Frames::default();
+    assert!(!parser.screen().synchronized_output());
+    parser.process_cb(b"\x1b[?2026hfoo", &mut frames);
+    assert!(parser.screen().synchronized_output());
+    assert_eq!(frames.0, 0);
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?2026$p"),
+        b"\x1b[?2026;1$y"
+    );
+    parser.process_cb(b"bar\x1b[?2026l", &mut frames);
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(frames.0, 1);
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?2026$p"),
+        b"\x1b[?2026;2$y"
+    );
+
+    // ending an update which isn't in progress isn't a new frame
+    parser.process_cb(b"\x1b[?2026l", &mut frames);
+    assert_eq!(frames.0, 1);
+
+    // RIS ends the update too
+    parser.process_cb(b"\x1b[?2026h\x1b[?2026l\x1b[?2026h\x1bc", &mut frames);
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(frames.0, 3);
+
+    // synchronized output isn't part of the state to restore
+    parser.process(b"\x1b[?2026h");
+    assert_eq!(
+        parser.screen().input_mode_formatted(),
+        shpool_vt100::Parser::default()
+            .screen()
+            .input_mode_formatted()
+    );
+
+    let mut parser = shpool_vt100::Parser::default();
+    let mut frames = Frames::default();
+    parser.set_synchronized_output_limits(
+        std::time::Duration::from_secs(60),
+        20,
+    );
+    // only the bytes written during the update count towards the limit
+    parser.process_cb(b"01234567890123456789\x1b[?2026h", &mut frames);
+    assert!(parser.screen().synchronized_output());
+    parser.process_cb(b"01234567890123456789", &mut frames);
+    assert!(parser.screen().synchronized_output());
+    parser.process_cb(b"0123", &mut frames);
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(frames.0, 1);
+
+    // the byte count starts over with each update
+    parser.process_cb(b"\x1b[?2026h01\x1b[?2026l\x1b[?2026h", &mut frames);
+    assert_eq!(frames.0, 2);
+    parser.process_cb(b"0123", &mut frames);
+    assert!(parser.screen().synchronized_output());
+    assert!(!parser.end_expired_synchronized_output());
+    assert!(parser.screen().synchronized_output());
+
+    parser.set_synchronized_output_limits(std::time::Duration::ZERO, 20);
+    assert!(parser.end_expired_synchronized_output());
+    assert!(!parser.screen().synchronized_output());
+    parser.process(b"\x1b[?2026h");
+    assert!(!parser.screen().synchronized_output());
+}
+
+#[test]
+fn kitty_keyboard_flags() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[?u"), b"\x1b[?0u");
+
+    parser.process(b"\x1b[>1u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 1);
+    parser.process(b"\x1b[>31u\x1b[>255u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 31);
+    assert_eq!(helpers::replies(&mut parser, b"\x1b[?u"), b"\x1b[?31u");
+    parser.process(b"\x1b[<u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 31);
+    parser.process(b"\x1b[<1u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 1);
+    parser.process(b"\x1b[<5u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+
+    // CSI = u modifies the active entry
+    parser.process(b"\x1b[=5u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 5);
+    parser.process(b"\x1b[=2;2u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 7);
+    parser.process(b"\x1b[=1;3u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 6);
+    parser.process(b"\x1b[>1u\x1b[=8;1u\x1b[<u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 6);
+    parser.process(b"\x1b[<u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+
+    // the stack is bounded, dropping the oldest entries
+    for flags in 1..=10 {
+        parser.process(format!("\x1b[>{flags}u").as_bytes());
+    }
+    parser.process(b"\x1b[<7u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 3);
+    parser.process(b"\x1b[<u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+
+    // the main and alternate screens have separate stacks
+    parser.process(b"\x1b[>1u\x1b[?1049h");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+    parser.process(b"\x1b[>3u");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 3);
+    parser.process(b"\x1b[?1049l");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 1);
+    parser.process(b"\x1b[?1049h");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 3);
+
+    parser.process(b"\x1bc");
+    assert_eq!(parser.screen().kitty_keyboard_flags(), 0);
+
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[>1u\x1b[>3u");
+    assert!(parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[>1u\x1b[>3u"));
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[<u\x1b[>5u\x1b[>7u");
+    assert_eq!(
+        parser.screen().input_mode_diff(&screen),
+        b"\x1b[<1u\x1b[>5u\x1b[>7u"
+    );
+    assert_eq!(parser.screen().input_mode_diff(parser.screen()), b"");
+
+    helpers::assert_reproduces_state(b"\x1b[>1u\x1b[>3u");
+    helpers::assert_reproduces_state_from(
+        b"\x1b[<u\x1b[>5u",
+        b"\x1b[>1u\x1b[>3u\x1b[>9u",
+    );
+}
@@ -0,0 +1,364 @@
+mod helpers;
+
+use shpool_vt100::{
+    Modifiers, MouseButton, MouseEvent, MouseEventKind,
+    MouseProtocolEncoding, MouseProtocolMode,
+};
+
+const MODES: [(MouseProtocolMode, &[u8]); 7] = [
+    (MouseProtocolMode::None, b""),
+    (MouseProtocolMode::Press, b"\x1b[?9h"),
+    (MouseProtocolMode::PressRelease, b"\x1b[?1000h"),
+    (MouseProtocolMode::Highlight, b"\x1b[?1001h"),
+    (MouseProtocolMode::ButtonMotion, b"\x1b[?1002h"),
+    (MouseProtocolMode::AnyMotion, b"\x1b[?1003h"),
+    (MouseProtocolMode::DecLocator, b"\x1b[1'z"),
+];
+
+const ENCODINGS: [(MouseProtocolEncoding, &[u8]); 5] = [
+    (MouseProtocolEncoding::Default, b""),
+    (MouseProtocolEncoding::Utf8, b"\x1b[?1005h"),
+    (MouseProtocolEncoding::Sgr, b"\x1b[?1006h"),
+    (MouseProtocolEncoding::Urxvt, b"\x1b[?1015h"),
+    (MouseProtocolEncoding::SgrPixels, b"\x1b[?1016h"),
+];
+
+fn mouse_parser(
+    mode: MouseProtocolMode,
+    encoding: MouseProtocolEncoding,
+) -> shpool_vt100::Parser {
+    let mut parser = shpool_vt100::Parser::default();
+    for (m, seq) in MODES {
+        if m == mode {
+            parser.process(seq);
+        }
+    }
+    for (e, seq) in ENCODINGS {
+        if e == encoding {
+            parser.process(seq);
+        }
+    }
+    assert_eq!(parser.screen().mouse_protocol_mode(), mode);
+    assert_eq!(parser.screen().mouse_protocol_encoding(), encoding);
+    parser
+}
+
+fn event(
+    kind: MouseEventKind,
+    button: Option<MouseButton>,
+    modifiers: Modifiers,
+) -> MouseEvent {
+    let mut event = MouseEvent::new(kind, button, (2, 4));
+    event.modifiers = modifiers;
+    event
+}
+
+#[test]
+fn modes_and_encodings() {
+    let ctrl = Modifiers {
+        ctrl: true,
+        ..Modifiers::default()
+    };
+    let events = [
+        (
+            "press",
+            event(MouseEventKind::Press, Some(MouseButton::Left), ctrl),
+        ),
+        (
+            "release",
+            event(MouseEventKind::Release, Some(MouseButton::Middle), ctrl),
+        ),
+        (
+            "button motion",
+            event(MouseEventKind::Motion, Some(MouseButton::Left), ctrl),
+        ),
+        (
+            "motion",
+            event(MouseEventKind::Motion, None, Modifiers::default()),
+        ),
+        (
+            "wheel",
+            event(MouseEventKind::Press, Some(MouseButton::WheelUp), ctrl),
+        ),
+        (
+            "wheel release",
+            event(MouseEventKind::Release, Some(MouseButton::WheelUp), ctrl),
+        ),
+    ];
+
+    for (mode, _) in MODES {
+        for (encoding, _) in ENCODINGS {
+            let parser = mouse_parser(mode, encoding);
+            for (name, event) in &events {
+                let reported = match *name {
+                    "press" | "wheel" => !matches!(
+                        mode,
+                        MouseProtocolMode::None
+                            | MouseProtocolMode::Highlight
+                            | MouseProtocolMode::DecLocator
+                    ),
+                    "release" => matches!(
+                        mode,
+                        MouseProtocolMode::PressRelease
+                            | MouseProtocolMode::ButtonMotion
+                            | MouseProtocolMode::AnyMotion
+                    ),
+                    "button motion" => matches!(
+                        mode,
+                        MouseProtocolMode::ButtonMotion
+                            | MouseProtocolMode::AnyMotion
+                    ),
+                    "motion" => mode == MouseProtocolMode::AnyMotion,
+                    "wheel release" => false,
+                    _ => unreachable!(),
+                };
+                let sgr = matches!(
+                    encoding,
+                    MouseProtocolEncoding::Sgr
+                        | MouseProtocolEncoding::SgrPixels
+                );
+                let urxvt = encoding == MouseProtocolEncoding::Urxvt;
+                let expected: &[u8] = if !reported {
+                    b""
+                } else if mode == MouseProtocolMode::Press {
+                    // x10 compatibility mode doesn't report modifiers
+                    match *name {
+                        "press" if sgr => b"\x1b[<0;5;3M",
+                        "press" if urxvt => b"\x1b[32;5;3M",
+                        "press" => b"\x1b[M %#",
+                        "wheel" if sgr => b"\x1b[<64;5;3M",
+                        "wheel" if urxvt => b"\x1b[96;5;3M",
+                        "wheel" => b"\x1b[M`%#",
+                        _ => unreachable!(),
+                    }
+                } else {
+                    match *name {
+                        "press" if sgr => b"\x1b[<16;5;3M",
+                        "press" if urxvt => b"\x1b[48;5;3M",
+                        "press" => b"\x1b[M0%#",
+                        "release" if sgr => b"\x1b[<17;5;3m",
+                        "release" if urxvt => b"\x1b[51;5;3M",
+                        "release" => b"\x1b[M3%#",
+                        "button motion" if sgr => b"\x1b[<48;5;3M",
+                        "button motion" if urxvt => b"\x1b[80;5;3M",
+                        "button motion" => b"\x1b[MP%#",
+                        "motion" if sgr => b"\x1b[<35;5;3M",
+                        "motion" if urxvt => b"\x1b[67;5;3M",
+                        "motion" => b"\x1b[MC%#",
+                        "wheel" if sgr => b"\x1b[<80;5;3M",
+                        "wheel" if urxvt => b"\x1b[112;5;3M",
+                        "wheel" => b"\x1b[Mp%#",
+                        _ => unreachable!(),
+                    }
+                };
+                assert_eq!(
+                    parser.screen().mouse_event(event),
+                    expected,
+                    "{name} with {mode:?} and {encoding:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn modifiers() {
+    let parser = mouse_parser(
+        MouseProtocolMode::PressRelease,
+        MouseProtocolEncoding::Sgr,
+    );
+    let mut event = MouseEvent::new(
+        MouseEventKind::Press,
+        Some(MouseButton::Right),
+        (0, 0),
+    );
+    assert_eq!(parser.screen().mouse_event(&event), b"\x1b[<2;1;1M");
+    event.modifiers.shift = true;
+    assert_eq!(parser.screen().mouse_event(&event), b"\x1b[<6;1;1M");
+    event.modifiers.alt = true;
+    assert_eq!(parser.screen().mouse_event(&event), b"\x1b[<14;1;1M");
+    event.modifiers.ctrl = true;
+    assert_eq!(parser.screen().mouse_event(&event), b"\x1b[<30;1;1M");
+    event.modifiers.super_ = true;
+    assert_eq!(parser.screen().mouse_event(&event), b"\x1b[<30;1;1M");
+
+    // the high bit of the button code is set for the largest values, which
+    // takes two bytes in the utf-8 encoding
+    let parser = mouse_parser(
+        MouseProtocolMode::AnyMotion,
+        MouseProtocolEncoding::Utf8,
+    );
+    let mut event = MouseEvent::new(
+        MouseEventKind::Motion,
+        Some(MouseButton::WheelRight),
+        (0, 0),
+    );
+    event.modifiers.shift = true;
+    event.modifiers.alt = true;
+    event.modifiers.ctrl = true;
+    assert_eq!(
+        parser.screen().mouse_event(&event),
+        "\x1b[M\u{9f}!!".as_bytes()
+    );
+}
+
+#[test]
+fn coordinate_limits() {
+    let press = |position| {
+        MouseEvent::new(
+            MouseEventKind::Press,
+            Some(MouseButton::Left),
+            position,
+        )
+    };
+
+    let parser = mouse_parser(
+        MouseProtocolMode::PressRelease,
+        MouseProtocolEncoding::Default,
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&press((222, 222))),
+        b"\x1b[M \xff\xff"
+    );
+    assert_eq!(parser.screen().mouse_event(&press((0, 223))), b"");
+    assert_eq!(parser.screen().mouse_event(&press((223, 0))), b"");
+
+    let parser = mouse_parser(
+        MouseProtocolMode::PressRelease,
+        MouseProtocolEncoding::Utf8,
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&press((94, 95))),
+        "\x1b[M \u{80}\u{7f}".as_bytes()
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&press((2014, 2014))),
+        "\x1b[M \u{7ff}\u{7ff}".as_bytes()
+    );
+    assert_eq!(parser.screen().mouse_event(&press((0, 2015))), b"");
+    assert_eq!(parser.screen().mouse_event(&press((2015, 0))), b"");
+
+    let parser = mouse_parser(
+        MouseProtocolMode::PressRelease,
+        MouseProtocolEncoding::Sgr,
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&press((u16::MAX, u16::MAX))),
+        b"\x1b[<0;65536;65536M"
+    );
+}
+
+#[test]
+fn mode_changes() {
+    for (mode, mode_seq) in MODES {
+        for (encoding, encoding_seq) in ENCODINGS {
+            let input = [mode_seq, encoding_seq].concat();
+            helpers::assert_reproduces_state(&input);
+            for (prev_mode, prev_mode_seq) in MODES {
+                for (prev_encoding, prev_encoding_seq) in ENCODINGS {
+                    if (mode, encoding) == (prev_mode, prev_encoding) {
+                        continue;
+                    }
+                    let prev_input =
+                        [prev_mode_seq, prev_encoding_seq].concat();
+                    helpers::assert_reproduces_state_from(
+                        &input,
+                        &prev_input,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(
+        helpers::replies(
+            &mut parser,
+            b"\x1b[?1001h\x1b[?1015h\x1b[?1001$p\x1b[?1015$p\x1b[?1016$p"
+        ),
+        b"\x1b[?1001;1$y\x1b[?1015;1$y\x1b[?1016;2$y"
+    );
+    parser.process(b"\x1b[?1001l\x1b[?1015l");
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        MouseProtocolMode::None
+    );
+    assert_eq!(
+        parser.screen().mouse_protocol_encoding(),
+        MouseProtocolEncoding::Default
+    );
+
+    parser.process(b"\x1b[2;1'z");
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        MouseProtocolMode::DecLocator
+    );
+    parser.process(b"\x1b['z");
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        MouseProtocolMode::None
+    );
+}
+
+#[test]
+fn alternate_scroll() {
+    let wheel =
+        |button| MouseEvent::new(MouseEventKind::Press, Some(button), (0, 0));
+
+    let mut parser = shpool_vt100::Parser::default();
+    assert!(!parser.screen().alternate_scroll());
+    parser.process(b"\x1b[?1049h");
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelUp)),
+        b""
+    );
+
+    parser.process(b"\x1b[?1007h");
+    assert!(parser.screen().alternate_scroll());
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b[?1007$p"),
+        b"\x1b[?1007;1$y"
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelUp)),
+        b"\x1b[A"
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelDown)),
+        b"\x1b[B"
+    );
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelLeft)),
+        b""
+    );
+    assert_eq!(parser.screen().mouse_event(&wheel(MouseButton::Left)), b"");
+    parser.process(b"\x1b[?1h");
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelUp)),
+        b"\x1bOA"
+    );
+
+    // mouse reporting takes precedence
+    parser.process(b"\x1b[?1000h");
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelUp)),
+        b"\x1b[M`!!"
+    );
+    parser.process(b"\x1b[?1000l");
+
+    // only applies to the alternate screen
+    parser.process(b"\x1b[?1049l");
+    assert_eq!(
+        parser.screen().mouse_event(&wheel(MouseButton::WheelUp)),
+        b""
+    );
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1007l");
+    assert!(!parser.screen().alternate_scroll());
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[?1007l");
+    assert_eq!(screen.input_mode_diff(parser.screen()), b"\x1b[?1007h");
+
+    helpers::assert_reproduces_state(b"\x1b[?1007h");
+    helpers::assert_reproduces_state_from(b"\x1b[?1007l", b"\x1b[?1007h");
+}
@@ -15,7 +15,525 @@ fn title_icon_name() {
     helpers::fixture("title_icon_name");
 }
 
+#[test]
+fn title_stack() {
+    fn titles(parser: &shpool_vt100::Parser) -> (&str, &str) {
+        (parser.screen().icon_name(), parser.screen().title())
+    }
+
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b]0;shell\x07\x1b[22t\x1b]0;vim\x07");
+    assert_eq!(titles(&parser), ("vim", "vim"));
+    parser.process(b"\x1b[23t");
+    assert_eq!(titles(&parser), ("shell", "shell"));
+    parser.process(b"\x1b[23t");
+    assert_eq!(titles(&parser), ("shell", "shell"));
+
+    // sub-selectors save and restore only the icon name or the title
+    parser.process(b"\x1b[22;2t\x1b]0;vim\x07\x1b[23;0t");
+    assert_eq!(titles(&parser), ("vim", "shell"));
+    parser.process(b"\x1b[22;1t\x1b]0;less\x07\x1b[23;2t");
+    assert_eq!(titles(&parser), ("less", "less"));
+    parser.process(b"\x1b[22;0t\x1b]0;man\x07\x1b[23;1t");
+    assert_eq!(titles(&parser), ("less", "man"));
+
+    // the stack is bounded, dropping the oldest entries
+    let mut parser = shpool_vt100::Parser::default();
+    for i in 0..12 {
+        parser.process(format!("\x1b]2;{i}\x07\x1b[22;2t").as_bytes());
+    }
+    for _ in 0..12 {
+        parser.process(b"\x1b[23;2t");
+    }
+    assert_eq!(titles(&parser), ("", "2"));
+
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(
+        b"\x1b]0;a\x07\x1b[22t\x1b]1;b\x07\x1b[22;1t\x1b]2;c\x07\x1b[22;2t\x1b]0;d\x07",
+    );
+    let screen = parser.screen().clone();
+    let mut new_parser = shpool_vt100::Parser::default();
+    new_parser.process(&screen.title_formatted());
+    assert_eq!(titles(&new_parser), ("d", "d"));
+    for expected in [("d", "c"), ("b", "c"), ("a", "a"), ("a", "a")] {
+        parser.process(b"\x1b[23t");
+        new_parser.process(b"\x1b[23t");
+        assert_eq!(titles(&parser), expected);
+        assert_eq!(titles(&new_parser), expected);
+    }
+
+    // RIS keeps the title stack, like the title itself
+    parser.process(b"\x1b[22t\x1b]0;e\x07\x1bc\x1b[23t");
+    assert_eq!(titles(&parser), ("a", "a"));
+
+    let mut prev_parser = shpool_vt100::Parser::default();
+    prev_parser.process(b"\x1b]0;a\x07\x1b[22t\x1b]0;x\x07\x1b[22;1t");
+    let mut new_parser = shpool_vt100::Parser::default();
+    new_parser.process(&prev_parser.screen().title_formatted());
+    new_parser.process(&screen.title_diff(prev_parser.screen()));
+    assert_eq!(titles(&new_parser), ("d", "d"));
+    for expected in [("d", "c"), ("b", "c"), ("a", "a")] {
+        new_parser.process(b"\x1b[23t");
+        assert_eq!(titles(&new_parser), expected);
+    }
+    assert_eq!(screen.title_diff(&screen), b"");
+}
+
 #[test]
 fn unknown_osc() {
     helpers::fixture("unknown_osc");
 }
+
+#[test]
+fn hyperlinks() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(
+        b"a\x1b]8;id=x;https://example.com/a;b\x07bc\x1b[1;31md\x1b[me\x1b]8;;\x07f",
+    );
+    let screen = parser.screen();
+    assert!(screen.hyperlink(screen.cell(0, 0).unwrap()).is_none());
+    for col in 1..5 {
+        let link = screen.hyperlink(screen.cell(0, col).unwrap()).unwrap();
+        assert_eq!(link.uri(), "https://example.com/a;b");
+        assert_eq!(link.id(), Some("x"));
+    }
+    assert!(screen.hyperlink(screen.cell(0, 5).unwrap()).is_none());
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        &b"\x1b[?25h\x1b[m\x1b[H\x1b[Ja\x1b]8;id=x;https://example.com/a;b\x07bc\x1b[31;1md\x1b[me\x1b]8;;\x07f"[..],
+    );
+
+    // links without an id are distinct from each other by uri
+    parser.process(
+        b"\r\n\x1b]8;;http://a\x07g\x1b]8;;http://b\x07h\x1b]8;;\x07",
+    );
+    let screen = parser.screen();
+    let link = screen.hyperlink(screen.cell(1, 0).unwrap()).unwrap();
+    assert_eq!(link.uri(), "http://a");
+    assert_eq!(link.id(), None);
+    let link = screen.hyperlink(screen.cell(1, 1).unwrap()).unwrap();
+    assert_eq!(link.uri(), "http://b");
+
+    // erased cells don't keep the link
+    parser.process(b"\x1b]8;;http://c\x07\x1b[K");
+    let screen = parser.screen();
+    assert!(screen.hyperlink(screen.cell(1, 2).unwrap()).is_none());
+    assert!(screen.hyperlink(screen.cell(1, 0).unwrap()).is_some());
+
+    let rows: Vec<_> = parser.screen().rows_formatted(0, 80).collect();
+    assert_eq!(
+        rows[1],
+        b"\x1b]8;;http://a\x07g\x1b]8;;http://b\x07h\x1b]8;;\x07"
+    );
+
+    helpers::assert_reproduces_state(
+        b"a\x1b]8;id=x;https://example.com\x07bc\x1b[1md\x1b[me\x1b]8;;\x07f",
+    );
+    helpers::assert_reproduces_state(b"\x1b]8;;http://a\x07abc\x1b[K");
+    helpers::assert_reproduces_state_from(
+        b"\x1b[Hx\x1b]8;;http://b\x07y\x1b]8;;\x07z",
+        b"\x1b]8;;http://a\x07abc\x1b]8;;\x07def",
+    );
+
+    // links which are no longer used are dropped once enough have been seen,
+    // but links still in use are kept
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b]8;;http://kept\x07a\x1b]8;;\x07\r\n");
+    for i in 0..5000 {
+        parser.process(format!("\x1b]8;;http://{i}\x07b\x08").as_bytes());
+    }
+    let screen = parser.screen();
+    let link = screen.hyperlink(screen.cell(0, 0).unwrap()).unwrap();
+    assert_eq!(link.uri(), "http://kept");
+    let link = screen.hyperlink(screen.cell(1, 0).unwrap()).unwrap();
+    assert_eq!(link.uri(), "http://4999");
+}
+
+#[test]
+fn palette() {
+    let mut parser = shpool_vt100::Parser::default();
+    assert_eq!(parser.screen().palette_color(1), None);
+    assert_eq!(parser.screen().palette_formatted(), b"");
+
+    parser.process(b"\x1b]4;1;rgb:ff/80/00;2;#102030\x07");
+    assert_eq!(parser.screen().palette_color(1), Some((255, 128, 0)));
+    assert_eq!(parser.screen().palette_color(2), Some((16, 32, 48)));
+    assert_eq!(parser.screen().palette_color(3), None);
+
+    // components can have between one and four hex digits
+    parser.process(b"\x1b]4;3;rgb:f/8/0\x1b\\\x1b]4;4;rgb:ffff/8000/0\x07");
+    assert_eq!(parser.screen().palette_color(3), Some((255, 136, 0)));
+    assert_eq!(parser.screen().palette_color(4), Some((255, 128, 0)));
+    parser.process(b"\x1b]4;5;#fff;6;#123456789abc\x07");
+    assert_eq!(parser.screen().palette_color(5), Some((240, 240, 240)));
+    assert_eq!(parser.screen().palette_color(6), Some((18, 86, 154)));
+
+    // invalid specifications are ignored
+    parser.process(b"\x1b]4;7;red;8;rgb:1/2;9;#12345\x07");
+    assert_eq!(parser.screen().palette_color(7), None);
+    assert_eq!(parser.screen().palette_color(8), None);
+    assert_eq!(parser.screen().palette_color(9), None);
+
+    parser.process(b"\x1b]10;rgb:ee/ee/ee;rgb:11/11/11\x07");
+    assert_eq!(parser.screen().foreground_color(), Some((238, 238, 238)));
+    assert_eq!(parser.screen().background_color(), Some((17, 17, 17)));
+    assert_eq!(parser.screen().cursor_color(), None);
+    parser.process(b"\x1b]12;#00ff00\x07");
+    assert_eq!(parser.screen().cursor_color(), Some((0, 255, 0)));
+
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b]4;1;?;200;?\x07"),
+        b"\x1b]4;1;rgb:ffff/8080/0000\x07\x1b]4;200;rgb:ffff/0000/d7d7\x07"
+    );
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b]11;?\x1b\\"),
+        b"\x1b]11;rgb:1111/1111/1111\x1b\\"
+    );
+
+    let full = parser.screen().clone();
+    helpers::assert_reproduces_state(
+        b"\x1b]4;1;rgb:ff/80/00\x07\x1b]10;#eee\x07\x1b]12;#0f0\x07",
+    );
+
+    parser.process(b"\x1b]104;1;2\x07\x1b]110\x07");
+    assert_eq!(parser.screen().palette_color(1), None);
+    assert_eq!(parser.screen().palette_color(2), None);
+    assert_eq!(parser.screen().palette_color(3), Some((255, 136, 0)));
+    assert_eq!(parser.screen().foreground_color(), None);
+    assert_eq!(parser.screen().background_color(), Some((17, 17, 17)));
+    assert_eq!(
+        parser.screen().palette_diff(&full),
+        b"\x1b]104;1\x07\x1b]104;2\x07\x1b]110\x07"
+    );
+
+    // unknown dynamic colors aren't answered
+    assert_eq!(helpers::replies(&mut parser, b"\x1b]10;?\x07"), b"");
+
+    parser.process(b"\x1b]104\x07\x1b]111\x07\x1b]112\x07");
+    assert_eq!(parser.screen().palette_color(3), None);
+    assert_eq!(parser.screen().background_color(), None);
+    assert_eq!(parser.screen().cursor_color(), None);
+    assert_eq!(
+        parser.screen().palette_diff(&full),
+        b"\x1b]104\x07\x1b]110\x07\x1b]111\x07\x1b]112\x07"
+    );
+
+    // unless defaults were configured for them
+    parser.set_default_dynamic_colors(None, Some((0, 0, 0)), None);
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b]10;?;?;?\x1b\\"),
+        b"\x1b]11;rgb:0000/0000/0000\x1b\\"
+    );
+    parser.process(b"\x1b]11;#202020\x07");
+    assert_eq!(
+        helpers::replies(&mut parser, b"\x1b]11;?\x07"),
+        b"\x1b]11;rgb:2020/2020/2020\x07"
+    );
+    parser.process(b"\x1b]111\x07");
+
+    helpers::assert_reproduces_state_from(
+        b"\x1b]104;1\x07\x1b]4;2;#000\x07\x1b]111\x07\x1b]10;#fff\x07",
+        b"\x1b]4;1;#111;2;#222\x07\x1b]11;#333\x07",
+    );
+}
+
+#[test]
+fn clipboard() {
+    #[derive(Default)]
+    struct Clipboard {
+        copied: Vec<(Vec<u8>, Vec<u8>)>,
+        pasted: Vec<Vec<u8>>,
+        contents: Option<Vec<u8>>,
+        replies: Vec<u8>,
+    }
+
+    impl shpool_vt100::Callbacks for Clipboard {
+        fn copy_to_clipboard(
+            &mut self,
+            _: &mut shpool_vt100::Screen,
+            selection: &[u8],
+            data: &[u8],
+        ) {
+            self.copied.push((selection.to_vec(), data.to_vec()));
+        }
+
+        fn paste_from_clipboard(
+            &mut self,
+            _: &mut shpool_vt100::Screen,
+            selection: &[u8],
+        ) -> Option<Vec<u8>> {
+            self.pasted.push(selection.to_vec());
+            self.contents.clone()
+        }
+
+        fn reply(&mut self, _: &mut shpool_vt100::Screen, reply: &[u8]) {
+            self.replies.extend_from_slice(reply);
+        }
+    }
+
+    let mut parser = shpool_vt100::Parser::default();
+    let mut clipboard = Clipboard::default();
+    let screen = parser.screen().clone();
+    parser.process_cb(
+        b"\x1b]52;c;aGVsbG8=\x07\x1b]52;pc;d29ybGQ\x1b\\\x1b]52;;YQ==\x07",
+        &mut clipboard,
+    );
+    assert_eq!(
+        clipboard.copied,
+        vec![
+            (b"c".to_vec(), b"hello".to_vec()),
+            (b"pc".to_vec(), b"world".to_vec()),
+            (b"s0".to_vec(), b"a".to_vec()),
+        ]
+    );
+    assert_eq!(parser.screen().state_diff(&screen), b"");
+
+    // empty data clears the selection, invalid data is ignored
+    clipboard.copied.clear();
+    parser.process_cb(b"\x1b]52;c;\x07\x1b]52;c;a!b=\x07", &mut clipboard);
+    assert_eq!(clipboard.copied, vec![(b"c".to_vec(), vec![])]);
+
+    // reads are only answered if the callback provides the contents
+    parser.process_cb(b"\x1b]52;c;?\x07", &mut clipboard);
+    assert_eq!(clipboard.pasted, vec![b"c".to_vec()]);
+    assert_eq!(clipboard.replies, b"");
+    clipboard.contents = Some(b"hi there".to_vec());
+    parser.process_cb(b"\x1b]52;p;?\x1b\\", &mut clipboard);
+    assert_eq!(clipboard.pasted, vec![b"c".to_vec(), b"p".to_vec()]);
+    assert_eq!(clipboard.replies, b"\x1b]52;p;aGkgdGhlcmU=\x1b\\");
+
+    // payloads over the limit are dropped in both directions
+    clipboard.copied.clear();
+    clipboard.replies.clear();
+    parser.set_max_clipboard_len(4);
+    parser.process_cb(
+        b"\x1b]52;c;aGVsbG8=\x07\x1b]52;c;aGVsbA==\x07\x1b]52;c;?\x07",
+        &mut clipboard,
+    );
+    assert_eq!(clipboard.copied, vec![(b"c".to_vec(), b"hell".to_vec())]);
+    assert_eq!(clipboard.replies, b"");
+
+    // without callbacks, nothing happens
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b]52;c;aGVsbG8=\x07");
+    assert_eq!(parser.screen().state_diff(&screen), b"");
+}
+
+#[test]
+fn working_directory() {
+    #[derive(Default)]
+    struct State {
+        changes: Vec<(Option<String>, Option<String>)>,
+    }
+
+    impl shpool_vt100::Callbacks for State {
+        fn working_directory(&mut self, screen: &mut shpool_vt100::Screen) {
+            self.changes.push((
+                screen.working_directory_host().map(str::to_string),
+                screen.working_directory().map(str::to_string),
+            ));
+        }
+    }
+
+    let mut parser = shpool_vt100::Parser::default();
+    let mut state = State::default();
+    assert_eq!(parser.screen().working_directory(), None);
+    assert_eq!(parser.screen().working_directory_host(), None);
+
+    let screen = parser.screen().clone();
+    parser.process_cb(b"\x1b]7;file://myhost/home/me\x07", &mut state);
+    assert_eq!(parser.screen().working_directory(), Some("/home/me"));
+    assert_eq!(parser.screen().working_directory_host(), Some("myhost"));
+    assert_eq!(parser.screen().state_diff(&screen), b"");
+
+    // reporting the same directory again isn't a change
+    parser.process_cb(b"\x1b]7;file://myhost/home/me\x1b\\", &mut state);
+    assert_eq!(
+        state.changes,
+        vec![(Some("myhost".to_string()), Some("/home/me".to_string()))]
+    );
+
+    state.changes.clear();
+    parser.process_cb(
+        b"\x1b]7;file:///tmp/a%20dir;x/%E2%98%83\x07",
+        &mut state,
+    );
+    assert_eq!(
+        parser.screen().working_directory(),
+        Some("/tmp/a dir;x/\u{2603}")
+    );
+    assert_eq!(parser.screen().working_directory_host(), None);
+    parser.process_cb(b"\x1b]7;kitty-shell-cwd://other/srv\x07", &mut state);
+    assert_eq!(parser.screen().working_directory(), Some("/srv"));
+    assert_eq!(parser.screen().working_directory_host(), Some("other"));
+    assert_eq!(state.changes.len(), 2);
+
+    // invalid uris are ignored
+    parser.process_cb(
+        b"\x1b]7;http://host/x\x07\x1b]7;file://host/%zz\x07\x1b]7;file://host/%ff\x07",
+        &mut state,
+    );
+    assert_eq!(parser.screen().working_directory(), Some("/srv"));
+    assert_eq!(state.changes.len(), 2);
+
+    // the working directory isn't reset by RIS, like the title
+    parser.process(b"\x1bc");
+    assert_eq!(parser.screen().working_directory(), Some("/srv"));
+}
+
+#[test]
+fn semantic_prompts() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(
+        b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n\x1b]133;D;0\x07",
+    );
+    assert_eq!(
+        parser.screen().semantic_zone(0, 0),
+        shpool_vt100::SemanticZone::Prompt
+    );
+    assert_eq!(
+        parser.screen().semantic_zone(0, 2),
+        shpool_vt100::SemanticZone::Input
+    );
+    assert_eq!(
+        parser.screen().semantic_zone(1, 0),
+        shpool_vt100::SemanticZone::Output
+    );
+    assert_eq!(
+        parser.screen().semantic_zone(0, 4),
+        shpool_vt100::SemanticZone::None
+    );
+
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].prompt_start(), Some((0, 0)));
+    assert_eq!(commands[0].input_start(), Some((0, 2)));
+    assert_eq!(commands[0].output_start(), Some((1, 0)));
+    assert_eq!(commands[0].end(), (2, 1));
+    assert_eq!(commands[0].exit_status(), Some(0));
+    assert_eq!(
+        parser.screen().command_input(&commands[0]).as_deref(),
+        Some("ls")
+    );
+    assert_eq!(
+        parser.screen().last_command_output().as_deref(),
+        Some("a\nb")
+    );
+
+    // a command without output, and a prompt with a continuation line
+    parser.process(
+        b"\x1b]133;A\x07$ \x1b]133;B\x07true\r\n\x1b]133;C\x07\x1b]133;D;1\x07\
+          \x1b]133;A\x07$ \x1b]133;B\x07echo \\\r\n\x1b]133;P;k=s\x07> \
+          \x1b]133;B\x07x\r\n\x1b]133;C\x07x\r\n",
+    );
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 3);
+    assert_eq!(commands[1].prompt_start(), Some((3, 0)));
+    assert_eq!(commands[1].output_start(), None);
+    assert_eq!(commands[1].exit_status(), Some(1));
+    assert_eq!(
+        parser.screen().command_input(&commands[1]).as_deref(),
+        Some("true")
+    );
+    assert_eq!(commands[2].prompt_start(), Some((4, 0)));
+    assert_eq!(commands[2].exit_status(), None);
+    assert_eq!(
+        parser.screen().command_input(&commands[2]).as_deref(),
+        Some("echo \\\n> x")
+    );
+    assert_eq!(parser.screen().last_command_output().as_deref(), Some("x"));
+
+    // erasing doesn't keep the zone, and SGR doesn't reset it
+    parser.process(b"\x1b[m\x1b[Ky\x1b[2K");
+    assert_eq!(
+        parser.screen().semantic_zone(7, 0),
+        shpool_vt100::SemanticZone::None
+    );
+    parser.process(b"z");
+    assert_eq!(
+        parser.screen().semantic_zone(7, 1),
+        shpool_vt100::SemanticZone::Output
+    );
+
+    // the last command ends once D is seen, even before the next prompt
+    parser.process(b"\x1b]133;D;2\x07");
+    assert_eq!(parser.screen().commands()[2].exit_status(), Some(2));
+
+    helpers::assert_reproduces_state(
+        b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\n\x1b]133;D;0\x07\
+          \x1b]133;A\x07$ \x1b]133;B\x07x \\\r\n\x1b]133;P;k=s\x07> \
+          \x1b]133;B\x07y\r\n\x1b]133;C\x07b\x1b]133;D;1\x07",
+    );
+    helpers::assert_reproduces_state(
+        b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\n\x1b]133;D;0\x07\
+          \x1b]133;A\x07",
+    );
+    helpers::assert_reproduces_state_from(
+        b"\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07pwd\r\n",
+        b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\n",
+    );
+}
+
+#[test]
+fn semantic_prompt_scrolling() {
+    let mut parser = shpool_vt100::Parser::new(3, 10, 20);
+    for i in 0..4 {
+        parser.process(
+            format!(
+                "\x1b]133;A\x07$ \x1b]133;B\x07c{i}\r\n\x1b]133;C\x07o{i}\r\n\
+                 o{i}\r\n\x1b]133;D;0\x07"
+            )
+            .as_bytes(),
+        );
+    }
+    parser.process(b"\x1b]133;A\x07$ ");
+    let prompts: Vec<_> = parser
+        .screen()
+        .commands()
+        .iter()
+        .map(|command| command.prompt_start().unwrap().0)
+        .collect();
+    assert_eq!(prompts, vec![0, 3, 6, 9, 12]);
+    assert_eq!(
+        parser.screen().last_command_output().as_deref(),
+        Some("o3\no3")
+    );
+
+    // the screen shows rows 10 through 12
+    assert_eq!(parser.screen().scrollback(), 0);
+    assert!(parser.screen_mut().scroll_to_previous_prompt());
+    assert_eq!(parser.screen().scrollback(), 1);
+    assert_eq!(parser.screen().contents(), "$ c3\no3\no3");
+    assert!(parser.screen_mut().scroll_to_previous_prompt());
+    assert_eq!(parser.screen().scrollback(), 4);
+    assert!(parser.screen_mut().scroll_to_previous_prompt());
+    assert!(parser.screen_mut().scroll_to_previous_prompt());
+    assert_eq!(parser.screen().scrollback(), 10);
+    assert_eq!(parser.screen().contents(), "$ c0\no0\no0");
+    assert!(!parser.screen_mut().scroll_to_previous_prompt());
+    assert_eq!(parser.screen().scrollback(), 10);
+
+    assert!(parser.screen_mut().scroll_to_next_prompt());
+    assert_eq!(parser.screen().scrollback(), 7);
+    assert_eq!(parser.screen().contents(), "$ c1\no1\no1");
+    assert!(parser.screen_mut().scroll_to_next_prompt());
+    assert!(parser.screen_mut().scroll_to_next_prompt());
+    assert_eq!(parser.screen().scrollback(), 1);
+    assert!(parser.screen_mut().scroll_to_next_prompt());
+    assert_eq!(parser.screen().scrollback(), 0);
+    assert!(!parser.screen_mut().scroll_to_next_prompt());
+
+    // the marks move along with the text when it is reflowed
+    parser.screen_mut().set_size(3, 3);
+    let prompts: Vec<_> = parser
+        .screen()
+        .commands()
+        .iter()
+        .map(|command| command.prompt_start().unwrap().0)
+        .collect();
+    assert_eq!(prompts, vec![0, 4, 8, 12, 16]);
+    assert_eq!(
+        parser.screen().last_command_output().as_deref(),
+        Some("o3\no3")
+    );
+}
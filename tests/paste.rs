@@ -0,0 +1,82 @@
+use shpool_vt100::Paste;
+
+#[test]
+fn unbracketed() {
+    let parser = shpool_vt100::Parser::default();
+    assert!(!parser.screen().bracketed_paste());
+    assert_eq!(parser.screen().paste(&Paste::new("")), b"");
+    assert_eq!(parser.screen().paste(&Paste::new("foo bar")), b"foo bar");
+    assert_eq!(
+        parser.screen().paste(&Paste::new("a\nb\r\nc\rd")),
+        b"a\rb\rc\rd"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .paste(&Paste::new("a\tb\x1b[31mc\x03d\x7fe")),
+        b"a\tb[31mcde"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .paste(&Paste::new("a\u{9b}31mb\u{85}c\u{e9}")),
+        "a31mbc\u{e9}".as_bytes()
+    );
+    assert_eq!(
+        parser.screen().paste(&Paste::new("rm -rf ~\x1b[201~\n")),
+        b"rm -rf ~[201~\r"
+    );
+
+    let mut paste = Paste::new("a\x1bb\x03\nc");
+    paste.strip_controls = false;
+    assert_eq!(parser.screen().paste(&paste), b"a\x1bb\x03\rc");
+}
+
+#[test]
+fn bracketed() {
+    let mut parser = shpool_vt100::Parser::default();
+    parser.process(b"\x1b[?2004h");
+    assert!(parser.screen().bracketed_paste());
+    assert_eq!(
+        parser.screen().paste(&Paste::new("")),
+        b"\x1b[200~\x1b[201~"
+    );
+    assert_eq!(
+        parser.screen().paste(&Paste::new("foo\nbar\r\n")),
+        b"\x1b[200~foo\rbar\r\x1b[201~"
+    );
+
+    // control characters are left alone, since the application knows that
+    // the text was pasted
+    assert_eq!(
+        parser.screen().paste(&Paste::new("a\x1b[31mb\x03")),
+        b"\x1b[200~a\x1b[31mb\x03\x1b[201~"
+    );
+
+    // but the text can't end the paste early
+    assert_eq!(
+        parser.screen().paste(&Paste::new("a\x1b[201~\nrm -rf ~\n")),
+        b"\x1b[200~a\rrm -rf ~\r\x1b[201~"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .paste(&Paste::new("a\x1b[20\x1b[201~1~\x1b[201\x1b[201~~b")),
+        b"\x1b[200~ab\x1b[201~"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .paste(&Paste::new("a\u{9b}201~b\u{9b}20\x1b[201~1~c")),
+        b"\x1b[200~abc\x1b[201~"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .paste(&Paste::new("a\x1b[20\u{9b}201~1~b\u{9b}")),
+        "\x1b[200~ab\u{9b}\x1b[201~".as_bytes()
+    );
+
+    parser.process(b"\x1b[?2004l");
+    assert_eq!(parser.screen().paste(&Paste::new("a\x1bb")), b"ab");
+}
@@ -93,6 +93,26 @@ fn scrollback() {
     assert_eq!(parser.screen().contents(), "10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n21\n22\n23\n24\n25\n26\n27\n28\n29\n30\n31\n32\n33");
 }
 
+#[test]
+fn scrollback_larger_than_screen() {
+    let mut parser = shpool_vt100::Parser::new(3, 10, 20);
+    parser.process(b"1\r\n2\r\n3\r\n4\r\n5\r\n6\r\n7\r\n8\r\n9\r\n10");
+    assert_eq!(parser.screen().contents(), "8\n9\n10");
+
+    parser.screen_mut().set_scrollback(2);
+    assert_eq!(parser.screen().contents(), "6\n7\n8");
+
+    // once the offset is at least the screen height, only rows from the
+    // scrollback are visible
+    parser.screen_mut().set_scrollback(3);
+    assert_eq!(parser.screen().contents(), "5\n6\n7");
+    parser.screen_mut().set_scrollback(5);
+    assert_eq!(parser.screen().contents(), "3\n4\n5");
+    assert_eq!(parser.screen().rows(0, 10).count(), 3);
+    parser.screen_mut().set_scrollback(7);
+    assert_eq!(parser.screen().contents(), "1\n2\n3");
+}
+
 #[test]
 fn edge_of_screen() {
     let mut parser = shpool_vt100::Parser::default();